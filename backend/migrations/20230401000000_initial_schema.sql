@@ -0,0 +1,57 @@
+-- Schema the toplist was originally run against. IF NOT EXISTS lets existing databases adopt the migrations
+CREATE TABLE IF NOT EXISTS map
+(
+    map_name VARCHAR(255) NOT NULL,
+    times_played INT NULL DEFAULT 0,
+    time_added DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (map_name)
+);
+
+CREATE TABLE IF NOT EXISTS course
+(
+    course_id INT NOT NULL AUTO_INCREMENT,
+    course_name VARCHAR(255) NOT NULL,
+    mapfk VARCHAR(255) NOT NULL,
+    reverse TINYINT(1) NOT NULL DEFAULT 0,
+    PRIMARY KEY (course_id),
+    INDEX idx_course_mapfk (mapfk, course_name),
+    CONSTRAINT fk_course_map FOREIGN KEY (mapfk) REFERENCES map (map_name) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS player
+(
+    steamid VARCHAR(32) NOT NULL,
+    playername VARCHAR(255) NOT NULL,
+    PRIMARY KEY (steamid)
+);
+
+-- course_time is stored as HH:MM:SS.mmm, which sorts the same as the times themselves
+CREATE TABLE IF NOT EXISTS record
+(
+    record_key INT NOT NULL AUTO_INCREMENT,
+    steamidfk VARCHAR(32) NOT NULL,
+    courseidfk INT NOT NULL,
+    course_time VARCHAR(16) NOT NULL,
+    date_set DATETIME NOT NULL,
+    PRIMARY KEY (record_key),
+    UNIQUE INDEX idx_record_steamidfk (steamidfk, courseidfk),
+    INDEX idx_record_course_time (courseidfk, course_time),
+    INDEX idx_record_date_set (date_set),
+    CONSTRAINT fk_record_player FOREIGN KEY (steamidfk) REFERENCES player (steamid),
+    CONSTRAINT fk_record_course FOREIGN KEY (courseidfk) REFERENCES course (course_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS record_cp
+(
+    record_key INT NOT NULL AUTO_INCREMENT,
+    steamidfk VARCHAR(32) NOT NULL,
+    courseidfk INT NOT NULL,
+    course_time VARCHAR(16) NOT NULL,
+    date_set DATETIME NOT NULL,
+    PRIMARY KEY (record_key),
+    UNIQUE INDEX idx_record_cp_steamidfk (steamidfk, courseidfk),
+    INDEX idx_record_cp_course_time (courseidfk, course_time),
+    INDEX idx_record_cp_date_set (date_set),
+    CONSTRAINT fk_record_cp_player FOREIGN KEY (steamidfk) REFERENCES player (steamid),
+    CONSTRAINT fk_record_cp_course FOREIGN KEY (courseidfk) REFERENCES course (course_id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+-- Difficulty tier of each map, used by the points formula in scoring.toml. Maps without a row use default_tier
+CREATE TABLE IF NOT EXISTS map_tier
+(
+    mapfk VARCHAR(255) NOT NULL,
+    tier TINYINT UNSIGNED NOT NULL,
+    PRIMARY KEY (mapfk),
+    CONSTRAINT fk_map_tier_map FOREIGN KEY (mapfk) REFERENCES map (map_name) ON DELETE CASCADE
+);
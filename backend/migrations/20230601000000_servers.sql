@@ -0,0 +1,30 @@
+-- Game servers allowed to submit records. Keys are <server_id>.<secret> and only the argon2 hash of the secret is
+-- stored, servers are created and their keys rotated or revoked through the /api/admin/servers endpoints
+CREATE TABLE IF NOT EXISTS servers
+(
+    server_id INT NOT NULL AUTO_INCREMENT,
+    name VARCHAR(64) NOT NULL,
+    key_hash VARCHAR(255) NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    key_rotated_at DATETIME NULL,
+    revoked_at DATETIME NULL,
+    PRIMARY KEY (server_id)
+);
+
+-- Server each personal best was submitted from, NULL for records that predate submissions. MySQL has no
+-- ADD COLUMN IF NOT EXISTS, so the ALTERs only run when information_schema doesn't list the column yet
+SET @add_record_serverfk = IF(EXISTS(SELECT 1 FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'record' AND COLUMN_NAME = 'serverfk'),
+    'DO 0',
+    'ALTER TABLE record ADD COLUMN serverfk INT NULL, ADD INDEX idx_record_serverfk (serverfk),
+        ADD CONSTRAINT fk_record_server FOREIGN KEY (serverfk) REFERENCES servers (server_id)');
+PREPARE add_record_serverfk FROM @add_record_serverfk;
+EXECUTE add_record_serverfk;
+DEALLOCATE PREPARE add_record_serverfk;
+
+SET @add_record_cp_serverfk = IF(EXISTS(SELECT 1 FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'record_cp' AND COLUMN_NAME = 'serverfk'),
+    'DO 0',
+    'ALTER TABLE record_cp ADD COLUMN serverfk INT NULL, ADD INDEX idx_record_cp_serverfk (serverfk),
+        ADD CONSTRAINT fk_record_cp_server FOREIGN KEY (serverfk) REFERENCES servers (server_id)');
+PREPARE add_record_cp_serverfk FROM @add_record_cp_serverfk;
+EXECUTE add_record_cp_serverfk;
+DEALLOCATE PREPARE add_record_cp_serverfk;
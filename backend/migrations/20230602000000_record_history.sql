@@ -0,0 +1,46 @@
+-- Every personal best a player has set on a course. record and record_cp only keep the current one, so the
+-- triggers below copy each new or improved time into this table whenever a record is ingested
+CREATE TABLE IF NOT EXISTS record_history
+(
+    history_key INT NOT NULL AUTO_INCREMENT,
+    steamidfk VARCHAR(32) NOT NULL,
+    courseidfk INT NOT NULL,
+    checkpoints TINYINT(1) NOT NULL,
+    course_time VARCHAR(16) NOT NULL,
+    date_set DATETIME NOT NULL,
+    serverfk INT NULL,
+    PRIMARY KEY (history_key),
+    INDEX idx_record_history_player_course (steamidfk, courseidfk, checkpoints, date_set),
+    INDEX idx_record_history_course (courseidfk, checkpoints, date_set),
+    INDEX idx_record_history_serverfk (serverfk),
+    CONSTRAINT fk_record_history_server FOREIGN KEY (serverfk) REFERENCES servers (server_id)
+);
+
+-- Start the history off with the current personal bests, unless there already is one. Both tables go in one INSERT so
+-- the check sees the history as it was before the migration
+INSERT INTO record_history (steamidfk, courseidfk, checkpoints, course_time, date_set, serverfk)
+SELECT steamidfk, courseidfk, 0, course_time, date_set, serverfk FROM record WHERE NOT EXISTS (SELECT 1 FROM record_history)
+UNION ALL
+SELECT steamidfk, courseidfk, 1, course_time, date_set, serverfk FROM record_cp WHERE NOT EXISTS (SELECT 1 FROM record_history);
+
+-- Dropped first since MySQL only has CREATE TRIGGER IF NOT EXISTS from 8.0.29 on
+DROP TRIGGER IF EXISTS record_history_insert;
+DROP TRIGGER IF EXISTS record_history_update;
+DROP TRIGGER IF EXISTS record_cp_history_insert;
+DROP TRIGGER IF EXISTS record_cp_history_update;
+
+CREATE TRIGGER record_history_insert AFTER INSERT ON record FOR EACH ROW
+    INSERT INTO record_history (steamidfk, courseidfk, checkpoints, course_time, date_set, serverfk)
+    VALUES (NEW.steamidfk, NEW.courseidfk, 0, NEW.course_time, NEW.date_set, NEW.serverfk);
+
+CREATE TRIGGER record_history_update AFTER UPDATE ON record FOR EACH ROW
+    INSERT INTO record_history (steamidfk, courseidfk, checkpoints, course_time, date_set, serverfk)
+    SELECT NEW.steamidfk, NEW.courseidfk, 0, NEW.course_time, NEW.date_set, NEW.serverfk FROM DUAL WHERE NEW.course_time <> OLD.course_time;
+
+CREATE TRIGGER record_cp_history_insert AFTER INSERT ON record_cp FOR EACH ROW
+    INSERT INTO record_history (steamidfk, courseidfk, checkpoints, course_time, date_set, serverfk)
+    VALUES (NEW.steamidfk, NEW.courseidfk, 1, NEW.course_time, NEW.date_set, NEW.serverfk);
+
+CREATE TRIGGER record_cp_history_update AFTER UPDATE ON record_cp FOR EACH ROW
+    INSERT INTO record_history (steamidfk, courseidfk, checkpoints, course_time, date_set, serverfk)
+    SELECT NEW.steamidfk, NEW.courseidfk, 1, NEW.course_time, NEW.date_set, NEW.serverfk FROM DUAL WHERE NEW.course_time <> OLD.course_time;
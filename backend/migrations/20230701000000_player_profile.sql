@@ -0,0 +1,16 @@
+-- Steam profile of each player, filled in by the profile refresher so the records and leaderboard don't have to call
+-- Steam on every page view. profile_updated_at is NULL until the profile has been looked up for the first time.
+-- Skipped when the columns are already there, the same way as serverfk in the servers migration
+SET @add_player_profile = IF(EXISTS(SELECT 1 FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'player' AND COLUMN_NAME = 'profile_updated_at'),
+    'DO 0',
+    'ALTER TABLE player
+        ADD COLUMN personaname VARCHAR(255) NULL,
+        ADD COLUMN avatar VARCHAR(255) NULL,
+        ADD COLUMN avatarmedium VARCHAR(255) NULL,
+        ADD COLUMN avatarfull VARCHAR(255) NULL,
+        ADD COLUMN loccountrycode CHAR(2) NULL,
+        ADD COLUMN profile_updated_at DATETIME NULL,
+        ADD INDEX idx_player_profile_updated_at (profile_updated_at)');
+PREPARE add_player_profile FROM @add_player_profile;
+EXECUTE add_player_profile;
+DEALLOCATE PREPARE add_player_profile;
@@ -0,0 +1,105 @@
+-- The whole MySQL schema in SQLite's dialect. Dates are stored as 'YYYY-MM-DD HH:MM:SS' text so they sort and
+-- compare like MySQL's DATETIME, course_time is HH:MM:SS.mmm like in MySQL
+CREATE TABLE map
+(
+    map_name TEXT NOT NULL PRIMARY KEY,
+    times_played INTEGER NULL DEFAULT 0,
+    time_added TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE course
+(
+    course_id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    course_name TEXT NOT NULL,
+    mapfk TEXT NOT NULL REFERENCES map (map_name) ON DELETE CASCADE,
+    reverse INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX idx_course_mapfk ON course (mapfk, course_name);
+
+CREATE TABLE player
+(
+    steamid TEXT NOT NULL PRIMARY KEY,
+    playername TEXT NOT NULL
+);
+
+CREATE TABLE map_tier
+(
+    mapfk TEXT NOT NULL PRIMARY KEY REFERENCES map (map_name) ON DELETE CASCADE,
+    tier INTEGER NOT NULL
+);
+
+CREATE TABLE servers
+(
+    server_id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    key_hash TEXT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    key_rotated_at TEXT NULL,
+    revoked_at TEXT NULL
+);
+
+CREATE TABLE record
+(
+    record_key INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    steamidfk TEXT NOT NULL REFERENCES player (steamid),
+    courseidfk INTEGER NOT NULL REFERENCES course (course_id) ON DELETE CASCADE,
+    course_time TEXT NOT NULL,
+    date_set TEXT NOT NULL,
+    serverfk INTEGER NULL REFERENCES servers (server_id)
+);
+CREATE UNIQUE INDEX idx_record_steamidfk ON record (steamidfk, courseidfk);
+CREATE INDEX idx_record_course_time ON record (courseidfk, course_time);
+CREATE INDEX idx_record_date_set ON record (date_set);
+CREATE INDEX idx_record_serverfk ON record (serverfk);
+
+CREATE TABLE record_cp
+(
+    record_key INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    steamidfk TEXT NOT NULL REFERENCES player (steamid),
+    courseidfk INTEGER NOT NULL REFERENCES course (course_id) ON DELETE CASCADE,
+    course_time TEXT NOT NULL,
+    date_set TEXT NOT NULL,
+    serverfk INTEGER NULL REFERENCES servers (server_id)
+);
+CREATE UNIQUE INDEX idx_record_cp_steamidfk ON record_cp (steamidfk, courseidfk);
+CREATE INDEX idx_record_cp_course_time ON record_cp (courseidfk, course_time);
+CREATE INDEX idx_record_cp_date_set ON record_cp (date_set);
+CREATE INDEX idx_record_cp_serverfk ON record_cp (serverfk);
+
+CREATE TABLE record_history
+(
+    history_key INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    steamidfk TEXT NOT NULL,
+    courseidfk INTEGER NOT NULL,
+    checkpoints INTEGER NOT NULL,
+    course_time TEXT NOT NULL,
+    date_set TEXT NOT NULL,
+    serverfk INTEGER NULL REFERENCES servers (server_id)
+);
+CREATE INDEX idx_record_history_player_course ON record_history (steamidfk, courseidfk, checkpoints, date_set);
+CREATE INDEX idx_record_history_course ON record_history (courseidfk, checkpoints, date_set);
+CREATE INDEX idx_record_history_serverfk ON record_history (serverfk);
+
+CREATE TRIGGER record_history_insert AFTER INSERT ON record FOR EACH ROW
+BEGIN
+    INSERT INTO record_history (steamidfk, courseidfk, checkpoints, course_time, date_set, serverfk)
+    VALUES (NEW.steamidfk, NEW.courseidfk, 0, NEW.course_time, NEW.date_set, NEW.serverfk);
+END;
+
+CREATE TRIGGER record_history_update AFTER UPDATE ON record FOR EACH ROW WHEN NEW.course_time <> OLD.course_time
+BEGIN
+    INSERT INTO record_history (steamidfk, courseidfk, checkpoints, course_time, date_set, serverfk)
+    VALUES (NEW.steamidfk, NEW.courseidfk, 0, NEW.course_time, NEW.date_set, NEW.serverfk);
+END;
+
+CREATE TRIGGER record_cp_history_insert AFTER INSERT ON record_cp FOR EACH ROW
+BEGIN
+    INSERT INTO record_history (steamidfk, courseidfk, checkpoints, course_time, date_set, serverfk)
+    VALUES (NEW.steamidfk, NEW.courseidfk, 1, NEW.course_time, NEW.date_set, NEW.serverfk);
+END;
+
+CREATE TRIGGER record_cp_history_update AFTER UPDATE ON record_cp FOR EACH ROW WHEN NEW.course_time <> OLD.course_time
+BEGIN
+    INSERT INTO record_history (steamidfk, courseidfk, checkpoints, course_time, date_set, serverfk)
+    VALUES (NEW.steamidfk, NEW.courseidfk, 1, NEW.course_time, NEW.date_set, NEW.serverfk);
+END;
@@ -0,0 +1,9 @@
+-- Steam profile of each player, see the MySQL migration of the same name
+ALTER TABLE player ADD COLUMN personaname TEXT NULL;
+ALTER TABLE player ADD COLUMN avatar TEXT NULL;
+ALTER TABLE player ADD COLUMN avatarmedium TEXT NULL;
+ALTER TABLE player ADD COLUMN avatarfull TEXT NULL;
+ALTER TABLE player ADD COLUMN loccountrycode TEXT NULL;
+ALTER TABLE player ADD COLUMN profile_updated_at TEXT NULL;
+
+CREATE INDEX idx_player_profile_updated_at ON player (profile_updated_at);
@@ -0,0 +1,497 @@
+use crate::error::ApiError;
+use crate::extract::{Json, Path, Query};
+use crate::leaderboard;
+use crate::repository::ToplistRepository;
+use crate::request_id;
+use crate::scoring::ScoringConfig;
+use crate::servers::{Admin, AdminKey, ApiKey, Server};
+use crate::steam::SteamClient;
+use crate::wr_history;
+use axum::
+{
+    extract::{FromRef, State},
+    http::StatusCode,
+    middleware,
+    response::Redirect,
+    routing::{delete, get, post},
+    Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use toplist_types::
+{
+    Countries, CountriesQuery, Course, CourseName, CourseNames, CourseTime, HistoryEntry, HistoryQuery, Leaderboard, LeaderboardQuery, Map,
+    Maps, NewServer, PlayerRecord, PlayerRecords, Players, PurgedRecords, RecentQuery, RecentRecord, RecentRecords, RecordHistory,
+    RecordSubmission, Records, RecordsQuery, ServerCredentials, Servers, SteamId, SteamIdError, SteamResponse, SubmissionResult, WrHistory,
+};
+
+//NOTE: Shared by every handler, the repository, Steam client, scoring config and admin key are pulled out of it with State
+#[derive(Clone, FromRef)]
+pub struct AppState
+{
+    pub repository: Arc<dyn ToplistRepository>,
+    pub steam: Arc<dyn SteamClient>,
+    pub scoring: Arc<ScoringConfig>,
+    pub admin_key: AdminKey,
+}
+
+//NOTE: Path of the deprecated /api/records route. The map, course name and rflag segments are ignored since the course is looked up from the database
+#[derive(Deserialize)]
+struct LegacyRecordsParams
+{
+    checkpoints: bool,
+    courseid: i32,
+}
+
+//NOTE: The request and response bodies are in toplist-types, the defaults of the limits along with the queries
+const MAX_RECORDS_LIMIT: u32 = 500;
+const MAX_RECENT_LIMIT: u32 = 100;
+
+#[derive(Deserialize)]
+struct PlayerParams
+{
+    checkpoints: bool,
+    steamid: String,
+}
+
+async fn not_found() -> Result<(), ApiError>
+{
+    Err(ApiError::NotFound("route"))
+}
+
+pub fn router(state: AppState) -> Router
+{
+    Router::new()
+        .fallback(not_found)
+        .route("/", get(|| async { "Hello, Sailor!" }))
+        .route("/api/maps", get(get_maps))
+        .route("/api/:map", get(get_course_names))
+        .route("/api/:map/:course", get(get_course_data))
+        .route("/api/courses/:course_id/records", get(get_records))
+        .route("/api/courses/:course_id/wr-history", get(get_wr_history))
+        .route("/api/records", post(submit_record))
+        .route("/api/admin/servers", get(get_servers).post(create_server))
+        .route("/api/admin/servers/:server_id", delete(revoke_server))
+        .route("/api/admin/servers/:server_id/rotate", post(rotate_server_key))
+        .route("/api/admin/servers/:server_id/records", delete(purge_server_records))
+        .route("/api/records/:checkpoints/:map/:course/:courseid/:rflag", get(get_legacy_records))          //NOTE: Deprecated, redirects to /api/courses/:course_id/records
+        .route("/api/leaderboard", get(get_leaderboard))
+        .route("/api/countries", get(get_countries))
+        .route("/api/recent", get(get_recent_records))
+        .route("/api/player/info/:steamid", get(get_player_info))
+        .route("/api/player/records/:checkpoints/:steamid", get(get_player_records))
+        .route("/api/player/:steamid/course/:course_id/history", get(get_player_history))
+        .with_state(state)
+        .layer(middleware::from_fn(request_id::middleware))
+}
+
+//NOTE: Accepts SteamID2, SteamID3, SteamID64 and community profile URLs. Vanity URLs are resolved through the Steam API
+async fn resolve_steamid(steam: &dyn SteamClient, input: &str) -> Result<SteamId, ApiError>
+{
+    match input.parse::<SteamId>()
+    {
+        Err(SteamIdError::Vanity(name)) => steam.resolve_vanity(&name).await?.ok_or(ApiError::NotFound("player")),
+        steamid => Ok(steamid?),
+    }
+}
+
+//NOTE: Steam stores country codes in uppercase
+fn parse_country(country: &str) -> Result<String, ApiError>
+{
+    if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic())
+    {
+        return Err(ApiError::BadRequest("country has to be a two letter ISO 3166 code".to_string()));
+    }
+    Ok(country.to_ascii_uppercase())
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn submit_record(State(repository): State<Arc<dyn ToplistRepository>>, server: Server, Json(submission): Json<RecordSubmission>) -> Result<Json<SubmissionResult>, ApiError>
+{
+    let steamid = submission.steamid.parse::<SteamId>()?;
+    let player_name = submission.player_name.trim();
+    if player_name.is_empty()
+    {
+        return Err(ApiError::BadRequest("player_name can't be empty".to_string()));
+    }
+    if !submission.checkpoints && submission.teleports > 0
+    {
+        return Err(ApiError::BadRequest("pro runs can't have teleports".to_string()));
+    }
+    let course = repository.course(submission.course_id).await?.ok_or(ApiError::NotFound("course"))?;
+
+    let submitted = repository.submit_record(server.server_id, &course, steamid, player_name, submission.course_time, submission.checkpoints).await?;
+
+    tracing::info!("{} (server {}) submitted {} on course {} for {}, rank {}", server.name, server.server_id, submission.course_time, course.course_id, steamid, submitted.rank);
+
+    Ok(Json(SubmissionResult {
+        improved: submitted.improved,
+        rank: submitted.rank,
+        wr: submitted.improved && submitted.rank == 1,
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_servers(State(repository): State<Arc<dyn ToplistRepository>>, _admin: Admin) -> Result<Json<Servers>, ApiError>
+{
+    let servers = repository.servers().await?;
+
+    Ok(Json(Servers {
+        servers_count: servers.len(),
+        servers,
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn create_server(State(repository): State<Arc<dyn ToplistRepository>>, _admin: Admin, Json(new_server): Json<NewServer>) -> Result<Json<ServerCredentials>, ApiError>
+{
+    let name = new_server.name.trim();
+    if name.is_empty()
+    {
+        return Err(ApiError::BadRequest("name can't be empty".to_string()));
+    }
+
+    //NOTE: Only the secret is hashed, so the row is created along with the hash and the id it gets filled into the key
+    let mut key = ApiKey::generate(0);
+    key.server_id = repository.create_server(name, &key.hash()?).await?;
+
+    tracing::info!("Created server {} ({})", key.server_id, name);
+
+    Ok(Json(ServerCredentials {
+        server_id: key.server_id,
+        name: name.to_string(),
+        api_key: key.to_string(),
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn rotate_server_key(State(repository): State<Arc<dyn ToplistRepository>>, _admin: Admin, Path(server_id): Path<i32>) -> Result<Json<ServerCredentials>, ApiError>
+{
+    let key = ApiKey::generate(server_id);
+    let server = repository.rotate_server_key(server_id, &key.hash()?).await?.ok_or(ApiError::NotFound("server"))?;
+
+    tracing::info!("Rotated the API key of server {} ({})", server.server_id, server.name);
+
+    Ok(Json(ServerCredentials {
+        server_id: server.server_id,
+        name: server.name,
+        api_key: key.to_string(),
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn revoke_server(State(repository): State<Arc<dyn ToplistRepository>>, _admin: Admin, Path(server_id): Path<i32>) -> Result<StatusCode, ApiError>
+{
+    if !repository.revoke_server(server_id).await?
+    {
+        return Err(ApiError::NotFound("server"));
+    }
+
+    tracing::info!("Revoked the API key of server {}", server_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+//NOTE: Deletes every personal best a server submitted along with its history. Players lose those times entirely since
+//record and record_cp only keep the latest personal best
+#[axum_macros::debug_handler(state = AppState)]
+async fn purge_server_records(State(repository): State<Arc<dyn ToplistRepository>>, _admin: Admin, Path(server_id): Path<i32>) -> Result<Json<PurgedRecords>, ApiError>
+{
+    let purged = repository.purge_server_records(server_id).await?.ok_or(ApiError::NotFound("server"))?;
+
+    tracing::info!("Purged {} records and {} checkpoint records of server {}", purged.records, purged.records_cp, server_id);
+
+    Ok(Json(purged))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_player_info(State(steam): State<Arc<dyn SteamClient>>, Path(param): Path<String>) -> Result<Json<SteamResponse>, ApiError>
+{
+    let steamid = resolve_steamid(steam.as_ref(), &param).await?;
+    let players = steam.player_summaries(&[steamid]).await?;
+    Ok(Json(SteamResponse {
+        response: Players { players }
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_player_records(State(repository): State<Arc<dyn ToplistRepository>>, State(steam): State<Arc<dyn SteamClient>>, State(scoring): State<Arc<ScoringConfig>>, Path(PlayerParams { checkpoints, steamid }): Path<PlayerParams>) -> Result<Json<PlayerRecords>, ApiError>
+{
+    let steamid = resolve_steamid(steam.as_ref(), &steamid).await?;
+    let rows = repository.player_records(steamid, checkpoints).await?;
+
+    let player_records: Vec<PlayerRecord> = rows.into_iter().map(|row| PlayerRecord
+    {
+        diff: if row.rank == 1 { "WR".to_string() } else { (row.course_time - row.wr_time).to_string() },
+        points: scoring.points(row.rank, row.field_size, row.tier),
+        course_id: row.course_id,
+        map_name: row.map_name,
+        course_name: row.course_name,
+        course_time: row.course_time,
+        date: row.date,
+        rank: row.rank,
+        field_size: row.field_size,
+    }).collect();
+
+    Ok(Json(PlayerRecords {
+        records_count: player_records.len(),
+        points: player_records.iter().map(|record| record.points).sum(),
+        records: player_records,
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_player_history(State(repository): State<Arc<dyn ToplistRepository>>, State(steam): State<Arc<dyn SteamClient>>, Path((steamid, course_id)): Path<(String, i32)>, Query(query): Query<HistoryQuery>) -> Result<Json<RecordHistory>, ApiError>
+{
+    let steamid = resolve_steamid(steam.as_ref(), &steamid).await?;
+    let course = repository.course(course_id).await?.ok_or(ApiError::NotFound("course"))?;
+    let rows = repository.player_history(steamid, course_id, query.checkpoints).await?;
+
+    let mut previous: Option<CourseTime> = None;
+    let history: Vec<HistoryEntry> = rows.into_iter().map(|row|
+    {
+        let diff = previous.map(|previous| row.course_time - previous);
+        previous = Some(row.course_time);
+        HistoryEntry
+        {
+            course_time: row.course_time,
+            millis: row.course_time.as_millis(),
+            date: row.date,
+            timestamp: row.timestamp,
+            diff,
+        }
+    }).collect();
+
+    Ok(Json(RecordHistory {
+        course_id: course.course_id,
+        course_name: course.course_name,
+        map_name: course.mapfk,
+        history_count: history.len(),
+        history,
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_wr_history(State(repository): State<Arc<dyn ToplistRepository>>, Path(course_id): Path<i32>, Query(query): Query<HistoryQuery>) -> Result<Json<WrHistory>, ApiError>
+{
+    let course = repository.course(course_id).await?.ok_or(ApiError::NotFound("course"))?;
+    let history = repository.course_history(course_id, query.checkpoints).await?;
+
+    //NOTE: The clock can't be before 1970, falling back to 0 just makes the current WR stand for no time
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|now| now.as_secs() as i64).unwrap_or_default();
+    let wrs = wr_history::world_records(history, course.reverse != 0, now);
+
+    Ok(Json(WrHistory {
+        wrs_count: wrs.len(),
+        wrs,
+    }))
+}
+
+#[axum_macros::debug_handler]
+async fn get_legacy_records(Path(LegacyRecordsParams { checkpoints, courseid }): Path<LegacyRecordsParams>, Query(query): Query<RecordsQuery>) -> Redirect
+{
+    Redirect::permanent(&format!("/api/courses/{}/records?checkpoints={}&limit={}&offset={}&sort={}", courseid, checkpoints, query.limit, query.offset, query.sort.as_str()))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_records(State(repository): State<Arc<dyn ToplistRepository>>, Path(course_id): Path<i32>, Query(query): Query<RecordsQuery>) -> Result<Json<Records>, ApiError>
+{
+    let course = repository.course(course_id).await?.ok_or(ApiError::NotFound("course"))?;
+
+    let summary = repository.records_summary(&course, query.checkpoints).await?;
+    let mut map_records = repository.records(&course, query.checkpoints, query.sort, query.limit.min(MAX_RECORDS_LIMIT), query.offset).await?;
+
+    if let Some(wr_time) = summary.wr_time
+    {
+        for record in map_records.iter_mut()
+        {
+            if record.rank == 1
+            {
+                record.diff = "WR".to_string();
+            }
+            else
+            {
+                record.diff = (record.course_time - wr_time).to_string();
+            }
+        }
+    }
+
+    Ok(Json(Records {
+        records_count: summary.records_count as usize,
+        records: map_records,
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_leaderboard(State(repository): State<Arc<dyn ToplistRepository>>, State(scoring): State<Arc<ScoringConfig>>, Query(query): Query<LeaderboardQuery>) -> Result<Json<Leaderboard>, ApiError>
+{
+    let mut placements = repository.placements(query.checkpoints).await?;
+    if let Some(country) = query.country.as_deref().map(parse_country).transpose()?
+    {
+        placements.retain(|placement| placement.country.as_deref() == Some(country.as_str()));
+    }
+    let players = leaderboard::rank_players(placements, &scoring);
+
+    Ok(Json(Leaderboard {
+        players_count: players.len(),
+        players: players.into_iter().skip(query.offset as usize).take(query.limit.min(MAX_RECORDS_LIMIT) as usize).collect(),
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_countries(State(repository): State<Arc<dyn ToplistRepository>>, State(scoring): State<Arc<ScoringConfig>>, Query(query): Query<CountriesQuery>) -> Result<Json<Countries>, ApiError>
+{
+    let placements = repository.placements(query.checkpoints).await?;
+    let countries = leaderboard::rank_countries(&leaderboard::rank_players(placements, &scoring));
+
+    Ok(Json(Countries {
+        countries_count: countries.len(),
+        countries,
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_recent_records(State(repository): State<Arc<dyn ToplistRepository>>, Query(query): Query<RecentQuery>) -> Result<Json<RecentRecords>, ApiError>
+{
+    let rows = repository.recent_records(query.checkpoints, query.limit.min(MAX_RECENT_LIMIT)).await?;
+
+    let records: Vec<RecentRecord> = rows.into_iter().map(|row| RecentRecord
+    {
+        map_name: row.map_name,
+        course_id: row.course_id,
+        course_name: row.course_name,
+        player_name: row.player_name,
+        steamid: row.steamid,
+        course_time: row.course_time,
+        date: row.date,
+        wr: row.wr != 0,
+    }).collect();
+
+    Ok(Json(RecentRecords {
+        records_count: records.len(),
+        records,
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_maps(State(repository): State<Arc<dyn ToplistRepository>>) -> Result<Json<Maps>, ApiError>
+{
+    let maps_list: Vec<Map> = repository.maps().await?;
+
+    Ok(Json(Maps { maps: maps_list }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_course_names(State(repository): State<Arc<dyn ToplistRepository>>, Path(param): Path<String>) -> Result<Json<CourseNames>, ApiError>
+{
+    let map_course_names: Vec<CourseName> = repository.course_names(&param).await?;
+
+    Ok(Json(CourseNames {
+        course_count: map_course_names.len(),
+        course_names: map_course_names,
+    }))
+}
+
+#[axum_macros::debug_handler(state = AppState)]
+async fn get_course_data(State(repository): State<Arc<dyn ToplistRepository>>, Path(param): Path<(String, String)>) -> Result<Json<Course>, ApiError>
+{
+    let map_name = param.0;
+    let course_name = param.1;
+
+    let course_data = repository.course_by_name(&map_name, &course_name).await?.ok_or(ApiError::NotFound("course"))?;
+
+    Ok(Json(Course {
+        course_id: course_data.course_id,
+        course_name: course_data.course_name,
+        mapfk: course_data.mapfk,
+        reverse: course_data.reverse,
+    }))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::repository::memory::InMemoryRepository;
+    use toplist_types::RecordSort;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn player(account_id: u32) -> SteamId
+    {
+        SteamId::from_account_id(1, account_id).unwrap()
+    }
+
+    fn repository() -> (Arc<InMemoryRepository>, i32)
+    {
+        let repository = Arc::new(InMemoryRepository::new());
+        repository.add_map("kz_reverse", Some(2));
+        let course_id = repository.add_course("kz_reverse", "main", true);
+        repository.add_record(course_id, player(1), "player 1", "00:05:00.000".parse().unwrap(), false, DAY);
+        repository.add_record(course_id, player(2), "player 2", "00:06:00.000".parse().unwrap(), false, 2 * DAY);
+        repository.add_record(course_id, player(3), "player 3", "00:04:00.000".parse().unwrap(), false, 3 * DAY);
+        (repository, course_id)
+    }
+
+    #[tokio::test]
+    async fn records_are_ranked_with_diffs_to_the_wr()
+    {
+        let (repository, course_id) = repository();
+
+        let Json(records) = get_records(State(repository.clone()), Path(course_id), Query(RecordsQuery::default())).await.unwrap();
+
+        assert_eq!(records.records_count, 3);
+        assert_eq!(records.records.iter().map(|record| record.player_name.as_str()).collect::<Vec<&str>>(), ["player 2", "player 1", "player 3"]);
+        assert_eq!(records.records.iter().map(|record| record.diff.as_str()).collect::<Vec<&str>>(), ["WR", "-01:00.00", "-02:00.00"]);
+
+        let by_date = RecordsQuery { sort: RecordSort::Date, limit: 1, ..RecordsQuery::default() };
+        let Json(records) = get_records(State(repository.clone()), Path(course_id), Query(by_date)).await.unwrap();
+        assert_eq!(records.records.iter().map(|record| record.rank).collect::<Vec<u64>>(), [3]);
+
+        let missing = get_records(State(repository), Path(course_id + 1), Query(RecordsQuery::default())).await;
+        assert!(matches!(missing, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn submissions_only_keep_improvements()
+    {
+        let (repository, course_id) = repository();
+        let server = || Server { server_id: 1, name: "test server".to_string() };
+        let submission = |steamid: SteamId, time: &str, teleports: u32| Json(RecordSubmission
+        {
+            steamid: steamid.to_string(),
+            player_name: "player".to_string(),
+            course_id,
+            course_time: time.parse().unwrap(),
+            checkpoints: false,
+            teleports,
+        });
+
+        let Json(slower) = submit_record(State(repository.clone()), server(), submission(player(1), "00:04:30.000", 0)).await.unwrap();
+        assert!(!slower.improved && !slower.wr);
+        assert_eq!(slower.rank, 3);
+
+        let Json(wr) = submit_record(State(repository.clone()), server(), submission(player(1), "00:07:00.000", 0)).await.unwrap();
+        assert!(wr.improved && wr.wr);
+        assert_eq!(wr.rank, 1);
+
+        let teleports = submit_record(State(repository.clone()), server(), submission(player(1), "00:08:00.000", 2)).await;
+        assert!(matches!(teleports, Err(ApiError::BadRequest(_))));
+
+        let Json(history) = get_wr_history(State(repository), Path(course_id), Query(HistoryQuery::default())).await.unwrap();
+        assert_eq!(history.wrs.iter().map(|wr| wr.course_time.to_string()).collect::<Vec<String>>(), ["07:00.00", "06:00.00", "05:00.00"]);
+    }
+
+    #[tokio::test]
+    async fn leaderboard_is_paged()
+    {
+        let (repository, _) = repository();
+        let query = LeaderboardQuery { limit: 1, offset: 1, ..LeaderboardQuery::default() };
+
+        let Json(leaderboard) = get_leaderboard(State(repository), State(Arc::new(ScoringConfig::default())), Query(query)).await.unwrap();
+
+        assert_eq!(leaderboard.players_count, 3);
+        assert_eq!(leaderboard.players.len(), 1);
+        assert_eq!((leaderboard.players[0].rank, leaderboard.players[0].steamid), (2, player(1)));
+    }
+}
@@ -0,0 +1,220 @@
+use backend::import::{self, ImportError, LegacyRow, Report, Run};
+use clap::{Parser, ValueEnum};
+use sqlx::mysql::MySqlPool;
+use sqlx::{MySql, Transaction};
+use std::path::PathBuf;
+use toplist_types::CourseTime;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format
+{
+    Csv,
+    Sql,
+}
+
+#[derive(Parser)]
+#[command(about = "Imports records from legacy toplist SQL dumps and CSV exports into the toplist database")]
+struct Args
+{
+    #[arg(help = "SQL dump or CSV export to import")]
+    file: PathBuf,
+    #[arg(long, value_enum, help = "Format of the file, guessed from its extension when left out")]
+    format: Option<Format>,
+    #[arg(long, help = "Only import the rows of this table of an SQL dump")]
+    table: Option<String>,
+    #[arg(long, help = "Report what would be imported and roll everything back")]
+    dry_run: bool,
+}
+
+#[derive(Default)]
+struct Counts
+{
+    maps: u64,
+    courses: u64,
+    players: u64,
+    inserted: u64,
+    improved: u64,
+    skipped: u64,
+}
+
+#[derive(sqlx::FromRow)]
+struct ExistingCourse
+{
+    course_id: i32,
+    reverse: i8,
+}
+
+#[derive(sqlx::FromRow)]
+struct ExistingPlayer
+{
+    playername: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct ExistingRecord
+{
+    course_time: CourseTime,
+}
+
+fn read_rows(args: &Args) -> Result<Vec<LegacyRow>, ImportError>
+{
+    let format = match args.format
+    {
+        Some(format) => format,
+        None => match args.file.extension().and_then(|extension| extension.to_str()).map(str::to_lowercase).as_deref()
+        {
+            Some("csv") => Format::Csv,
+            Some("sql") => Format::Sql,
+            _ => return Err(ImportError::Sql("unknown file extension, pass --format".to_string())),
+        },
+    };
+
+    match format
+    {
+        Format::Csv => import::read_csv(std::fs::File::open(&args.file).map_err(ImportError::Io)?),
+        Format::Sql => import::read_sql_dump(&std::fs::read_to_string(&args.file).map_err(ImportError::Io)?, args.table.as_deref()),
+    }
+}
+
+//NOTE: Courses already in the database keep their reverse flag, the legacy one is only used for new courses
+async fn import_run(tx: &mut Transaction<'_, MySql>, run: &Run, report: &mut Report, counts: &mut Counts) -> Result<(), sqlx::Error>
+{
+    counts.maps += sqlx::query!(r#"INSERT IGNORE INTO map (map_name, times_played, time_added) VALUES (?, 0, NOW())"#, run.map_name).execute(&mut *tx).await?.rows_affected();
+
+    let course = sqlx::query_as!(ExistingCourse, r#"SELECT course_id, reverse FROM course WHERE mapfk=? AND course_name=?"#, run.map_name, run.course_name).fetch_optional(&mut *tx).await?;
+    let (course_id, reverse) = match course
+    {
+        Some(course) =>
+        {
+            if (course.reverse != 0) != run.reverse
+            {
+                report.conflicts.push(format!("line {}: {} {} is {}reverse in the database", run.line, run.map_name, run.course_name, if course.reverse != 0 { "" } else { "not " }));
+            }
+            (course.course_id, course.reverse != 0)
+        }
+        None =>
+        {
+            counts.courses += 1;
+            let course_id = sqlx::query!(r#"INSERT INTO course (course_name, mapfk, reverse) VALUES (?, ?, ?)"#, run.course_name, run.map_name, run.reverse).execute(&mut *tx).await?.last_insert_id();
+            (course_id as i32, run.reverse)
+        }
+    };
+
+    let steamid3 = run.steamid.steamid3();
+    //NOTE: Players already in the database keep their current name, the one in the export is older
+    let player = sqlx::query_as!(ExistingPlayer, r#"SELECT playername FROM player WHERE steamid=?"#, steamid3).fetch_optional(&mut *tx).await?;
+    match player
+    {
+        Some(player) if player.playername != run.player_name =>
+        {
+            report.conflicts.push(format!("line {}: {} is called {} in the database, kept it over {}", run.line, run.steamid.steamid2(), player.playername, run.player_name));
+        }
+        Some(_) => {}
+        None =>
+        {
+            counts.players += sqlx::query!(r#"INSERT IGNORE INTO player (steamid, playername) VALUES (?, ?)"#, steamid3, run.player_name).execute(&mut *tx).await?.rows_affected();
+        }
+    }
+
+    let date_set = run.date_set.map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string());
+    let existing = if run.checkpoints
+    {
+        sqlx::query_as!(ExistingRecord, r#"SELECT course_time AS "course_time: CourseTime" FROM record_cp WHERE steamidfk=? AND courseidfk=?"#, steamid3, course_id).fetch_optional(&mut *tx).await?
+    }
+    else
+    {
+        sqlx::query_as!(ExistingRecord, r#"SELECT course_time AS "course_time: CourseTime" FROM record WHERE steamidfk=? AND courseidfk=?"#, steamid3, course_id).fetch_optional(&mut *tx).await?
+    };
+
+    match existing
+    {
+        None =>
+        {
+            if run.checkpoints
+            {
+                sqlx::query!(r#"INSERT INTO record_cp (steamidfk, courseidfk, course_time, date_set) VALUES (?, ?, ?, COALESCE(?, NOW()))"#, steamid3, course_id, run.course_time, date_set).execute(&mut *tx).await?;
+            }
+            else
+            {
+                sqlx::query!(r#"INSERT INTO record (steamidfk, courseidfk, course_time, date_set) VALUES (?, ?, ?, COALESCE(?, NOW()))"#, steamid3, course_id, run.course_time, date_set).execute(&mut *tx).await?;
+            }
+            counts.inserted += 1;
+        }
+        Some(existing) if run.beats_time(reverse, existing.course_time) =>
+        {
+            if run.checkpoints
+            {
+                sqlx::query!(r#"UPDATE record_cp SET course_time=?, date_set=COALESCE(?, NOW()) WHERE steamidfk=? AND courseidfk=?"#, run.course_time, date_set, steamid3, course_id).execute(&mut *tx).await?;
+            }
+            else
+            {
+                sqlx::query!(r#"UPDATE record SET course_time=?, date_set=COALESCE(?, NOW()) WHERE steamidfk=? AND courseidfk=?"#, run.course_time, date_set, steamid3, course_id).execute(&mut *tx).await?;
+            }
+            counts.improved += 1;
+        }
+        Some(existing) =>
+        {
+            report.conflicts.push(format!("line {}: {} already has {} on {} {}, skipped {}", run.line, run.steamid.steamid2(), existing.course_time, run.map_name, run.course_name, run.course_time));
+            counts.skipped += 1;
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>>
+{
+    let args = Args::parse();
+    let rows = read_rows(&args)?;
+
+    let mut report = Report { rows: rows.len(), ..Report::default() };
+    let mut runs = Vec::new();
+    for row in &rows
+    {
+        match import::normalise(row)
+        {
+            Ok(run) => runs.push(run),
+            Err(error) => report.errors.push(error),
+        }
+    }
+    let runs = import::dedupe(runs, &mut report);
+
+    let pool = MySqlPool::connect(&std::env::var("DATABASE_URL")?).await?;
+    let mut tx = pool.begin().await?;
+    let mut counts = Counts::default();
+    for run in &runs
+    {
+        import_run(&mut tx, run, &mut report, &mut counts).await?;
+    }
+
+    //NOTE: A dry run goes through the same queries so the report matches a real import, the transaction just isn't kept
+    if args.dry_run
+    {
+        tx.rollback().await?;
+    }
+    else
+    {
+        tx.commit().await?;
+    }
+
+    //NOTE: The report is what the command outputs, so it goes to stdout with println instead of through tracing like the
+    //backend's logs
+    println!("{} rows read, {} unreadable, {} duplicates merged", report.rows, report.errors.len(), report.duplicates);
+    println!("{} records inserted, {} improved, {} skipped", counts.inserted, counts.improved, counts.skipped);
+    println!("{} new maps, {} new courses, {} new players", counts.maps, counts.courses, counts.players);
+    for error in &report.errors
+    {
+        println!("error: {}", error);
+    }
+    for conflict in &report.conflicts
+    {
+        println!("conflict: {}", conflict);
+    }
+    if args.dry_run
+    {
+        println!("Dry run, nothing was written");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,333 @@
+use crate::repository::PoolConfig;
+use crate::scoring::ScoringConfig;
+use axum::http::HeaderValue;
+use clap::builder::BoolishValueParser;
+use clap::Parser;
+use serde::Deserialize;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_CONFIG_FILE: &str = "toplist.toml";
+const DEFAULT_BIND_ADDRESS: ([u8; 4], u16) = ([127, 0, 0, 1], 3000);
+const DEFAULT_STEAM_CACHE_TTL: u64 = 60 * 60;
+const DEFAULT_PROFILE_REFRESH_INTERVAL: u64 = 10 * 60;
+const DEFAULT_PROFILE_MAX_AGE: u64 = 24 * 60 * 60;
+const DEFAULT_LOG_LEVEL: &str = "backend=info";
+const DEFAULT_SCORING_CONFIG: &str = "scoring.toml";
+
+//NOTE: Every setting can also be given in an environment variable or the config file. The command line wins over the
+//environment, which wins over the config file. Durations are in seconds
+#[derive(Parser, Debug, Default)]
+#[command(about = "Serves the toplist API")]
+pub struct Args
+{
+    #[arg(long, env = "TOPLIST_CONFIG", help = "TOML config file [default: toplist.toml, skipped when it doesn't exist]")]
+    pub config: Option<PathBuf>,
+    #[arg(long, env = "BIND_ADDRESS", help = "Address to listen on [default: 127.0.0.1:3000]")]
+    pub bind_address: Option<SocketAddr>,
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true, help = "mysql:// or sqlite:// URL of the database")]
+    pub database_url: Option<String>,
+    #[arg(long, env = "RUN_MIGRATIONS", num_args = 0..=1, default_missing_value = "true", value_parser = BoolishValueParser::new(), help = "Apply database migrations on startup [default: false]")]
+    pub run_migrations: Option<bool>,
+    #[arg(long, env = "DB_MAX_CONNECTIONS", help = "Most connections the pool opens [default: 10]")]
+    pub max_connections: Option<u32>,
+    #[arg(long, env = "DB_MIN_CONNECTIONS", help = "Connections the pool keeps open [default: 0]")]
+    pub min_connections: Option<u32>,
+    #[arg(long, env = "DB_ACQUIRE_TIMEOUT", help = "Seconds a query waits for a free connection [default: 30]")]
+    pub acquire_timeout: Option<u64>,
+    #[arg(long = "cors-origin", env = "CORS_ORIGINS", value_delimiter = ',', help = "Origin allowed to call the API, repeat for more or * for any [default: *]")]
+    pub cors_origins: Vec<String>,
+    #[arg(long, env = "STEAM_API_KEY", hide_env_values = true, help = "Steam Web API key, profiles and vanity URLs don't load without one")]
+    pub steam_api_key: Option<String>,
+    #[arg(long, env = "STEAM_CACHE_TTL", help = "Seconds Steam profiles and vanity URLs are cached for [default: 3600]")]
+    pub steam_cache_ttl: Option<u64>,
+    #[arg(long, env = "PROFILE_REFRESH_INTERVAL", help = "Seconds between looking for stale Steam profiles [default: 600]")]
+    pub profile_refresh_interval: Option<u64>,
+    #[arg(long, env = "PROFILE_MAX_AGE", help = "Seconds a stored Steam profile is kept before it's looked up again [default: 86400]")]
+    pub profile_max_age: Option<u64>,
+    #[arg(long, env = "RUST_LOG", help = "tracing filter, like info or backend=debug [default: backend=info]")]
+    pub log_level: Option<String>,
+    #[arg(long, env = "SCORING_CONFIG", help = "Points formula [default: scoring.toml]")]
+    pub scoring_config: Option<String>,
+    #[arg(long, env = "ADMIN_API_KEY", hide_env_values = true, help = "Key of the admin endpoints, they're disabled without one")]
+    pub admin_api_key: Option<String>,
+}
+
+//NOTE: The settings in the config file use the same names as the command line options
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile
+{
+    pub bind_address: Option<SocketAddr>,
+    pub database_url: Option<String>,
+    pub run_migrations: Option<bool>,
+    pub max_connections: Option<u32>,
+    pub min_connections: Option<u32>,
+    pub acquire_timeout: Option<u64>,
+    pub cors_origins: Option<Vec<String>>,
+    pub steam_api_key: Option<String>,
+    pub steam_cache_ttl: Option<u64>,
+    pub profile_refresh_interval: Option<u64>,
+    pub profile_max_age: Option<u64>,
+    pub log_level: Option<String>,
+    pub scoring_config: Option<String>,
+    pub admin_api_key: Option<String>,
+}
+
+pub struct Config
+{
+    pub bind_address: SocketAddr,
+    pub database_url: String,
+    pub run_migrations: bool,
+    pub pool: PoolConfig,
+    pub cors_origins: Vec<String>,  //NOTE: * allows any origin
+    pub steam_api_key: Option<String>,
+    pub steam_cache_ttl: Duration,
+    pub profile_refresh_interval: Duration,
+    pub profile_max_age: Duration,
+    pub log_level: String,
+    pub scoring: ScoringConfig,
+    pub admin_api_key: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError
+{
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            ConfigError::Io(path, e) => write!(f, "unable to read config file {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "unable to parse config file {}: {}", path.display(), e),
+            ConfigError::Invalid(problems) =>
+            {
+                write!(f, "invalid configuration:")?;
+                for problem in problems
+                {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigFile
+{
+    //NOTE: The default config file is optional, one passed with --config or TOPLIST_CONFIG has to exist
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError>
+    {
+        let (path, required) = match path
+        {
+            Some(path) => (path, true),
+            None => (Path::new(DEFAULT_CONFIG_FILE), false),
+        };
+
+        match std::fs::read_to_string(path)
+        {
+            Ok(contents) => toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_path_buf(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => Ok(ConfigFile::default()),
+            Err(e) => Err(ConfigError::Io(path.to_path_buf(), e)),
+        }
+    }
+}
+
+impl Config
+{
+    pub fn load(args: Args) -> Result<Self, ConfigError>
+    {
+        let file = ConfigFile::load(args.config.as_deref())?;
+        Config::resolve(args, file)
+    }
+
+    //NOTE: Reports every missing or invalid setting at once instead of stopping at the first one
+    pub fn resolve(args: Args, file: ConfigFile) -> Result<Self, ConfigError>
+    {
+        let mut problems = Vec::new();
+
+        let database_url = args.database_url.or(file.database_url).unwrap_or_else(||
+        {
+            problems.push("database_url is missing, pass --database-url, set DATABASE_URL or add database_url to the config file".to_string());
+            String::new()
+        });
+
+        let pool = PoolConfig
+        {
+            max_connections: args.max_connections.or(file.max_connections).unwrap_or(PoolConfig::default().max_connections),
+            min_connections: args.min_connections.or(file.min_connections).unwrap_or(PoolConfig::default().min_connections),
+            acquire_timeout: args.acquire_timeout.or(file.acquire_timeout).map(Duration::from_secs).unwrap_or(PoolConfig::default().acquire_timeout),
+        };
+        if pool.max_connections == 0
+        {
+            problems.push("max_connections has to be at least 1".to_string());
+        }
+        if pool.min_connections > pool.max_connections
+        {
+            problems.push(format!("min_connections ({}) can't be more than max_connections ({})", pool.min_connections, pool.max_connections));
+        }
+
+        let cors_origins = Some(args.cors_origins).filter(|origins| !origins.is_empty()).or(file.cors_origins).unwrap_or_else(|| vec!["*".to_string()]);
+        for origin in &cors_origins
+        {
+            if origin != "*" && (HeaderValue::from_str(origin).is_err() || !(origin.starts_with("http://") || origin.starts_with("https://")))
+            {
+                problems.push(format!("CORS origin {} has to be * or look like https://example.com", origin));
+            }
+        }
+
+        let profile_refresh_interval = args.profile_refresh_interval.or(file.profile_refresh_interval).unwrap_or(DEFAULT_PROFILE_REFRESH_INTERVAL);
+        if profile_refresh_interval == 0
+        {
+            problems.push("profile_refresh_interval has to be at least 1 second".to_string());
+        }
+
+        let log_level = args.log_level.or(file.log_level).unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string());
+        if let Err(e) = EnvFilter::try_new(&log_level)
+        {
+            problems.push(format!("log_level {} isn't a valid filter: {}", log_level, e));
+        }
+
+        let scoring_config = args.scoring_config.or(file.scoring_config).unwrap_or_else(|| DEFAULT_SCORING_CONFIG.to_string());
+        let scoring = ScoringConfig::load(&scoring_config).unwrap_or_else(|e|
+        {
+            problems.push(format!("{} ({})", e, scoring_config));
+            ScoringConfig::default()
+        });
+
+        if !problems.is_empty()
+        {
+            return Err(ConfigError::Invalid(problems));
+        }
+
+        Ok(Config
+        {
+            bind_address: args.bind_address.or(file.bind_address).unwrap_or_else(|| SocketAddr::from(DEFAULT_BIND_ADDRESS)),
+            database_url,
+            //NOTE: Off by default so the backend can run with a database user that can't alter the schema
+            run_migrations: args.run_migrations.or(file.run_migrations).unwrap_or(false),
+            pool,
+            cors_origins,
+            steam_api_key: args.steam_api_key.or(file.steam_api_key).filter(|key| !key.is_empty()),
+            steam_cache_ttl: Duration::from_secs(args.steam_cache_ttl.or(file.steam_cache_ttl).unwrap_or(DEFAULT_STEAM_CACHE_TTL)),
+            profile_refresh_interval: Duration::from_secs(profile_refresh_interval),
+            profile_max_age: Duration::from_secs(args.profile_max_age.or(file.profile_max_age).unwrap_or(DEFAULT_PROFILE_MAX_AGE)),
+            log_level,
+            scoring,
+            admin_api_key: args.admin_api_key.or(file.admin_api_key).filter(|key| !key.is_empty()),
+        })
+    }
+
+    pub fn cors_layer(&self) -> CorsLayer
+    {
+        if self.cors_origins.iter().any(|origin| origin == "*")
+        {
+            return CorsLayer::new().allow_origin(Any);
+        }
+        let origins: Vec<HeaderValue> = self.cors_origins.iter().filter_map(|origin| HeaderValue::from_str(origin).ok()).collect();
+        CorsLayer::new().allow_origin(AllowOrigin::list(origins))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn args() -> Args
+    {
+        Args { database_url: Some("sqlite::memory:".to_string()), ..Args::default() }
+    }
+
+    #[test]
+    fn uses_defaults()
+    {
+        let config = Config::resolve(args(), ConfigFile::default()).unwrap();
+
+        assert_eq!(config.bind_address, "127.0.0.1:3000".parse().unwrap());
+        assert_eq!(config.pool, PoolConfig::default());
+        assert_eq!(config.cors_origins, ["*"]);
+        assert!(!config.run_migrations && config.steam_api_key.is_none() && config.admin_api_key.is_none());
+        assert_eq!(config.steam_cache_ttl, Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn command_line_wins_over_the_file()
+    {
+        let file: ConfigFile = toml::from_str(r#"
+            bind_address = "0.0.0.0:8080"
+            database_url = "mysql://localhost/toplist"
+            max_connections = 20
+            cors_origins = ["https://kz.example.com"]
+            steam_api_key = "file key"
+        "#).unwrap();
+        let args = Args { max_connections: Some(5), steam_api_key: Some("cli key".to_string()), run_migrations: Some(true), ..args() };
+
+        let config = Config::resolve(args, file).unwrap();
+
+        assert_eq!(config.bind_address, "0.0.0.0:8080".parse().unwrap());
+        assert_eq!(config.database_url, "sqlite::memory:");
+        assert_eq!(config.pool.max_connections, 5);
+        assert_eq!(config.cors_origins, ["https://kz.example.com"]);
+        assert_eq!(config.steam_api_key.as_deref(), Some("cli key"));
+        assert!(config.run_migrations);
+    }
+
+    #[test]
+    fn parses_the_command_line()
+    {
+        let args = Args::try_parse_from(["backend", "--database-url", "sqlite://toplist.db", "--run-migrations", "--cors-origin", "https://a.example.com,https://b.example.com"]).unwrap();
+
+        assert_eq!(args.run_migrations, Some(true));
+        assert_eq!(args.cors_origins, ["https://a.example.com", "https://b.example.com"]);
+    }
+
+    #[test]
+    fn lists_every_problem()
+    {
+        let args = Args
+        {
+            min_connections: Some(4),
+            max_connections: Some(2),
+            cors_origins: vec!["kz.example.com".to_string()],
+            profile_refresh_interval: Some(0),
+            scoring_config: Some("Cargo.toml".to_string()),
+            ..Args::default()
+        };
+
+        let problems = match Config::resolve(args, ConfigFile::default())
+        {
+            Err(ConfigError::Invalid(problems)) => problems,
+            _ => panic!("expected the config to be invalid"),
+        };
+
+        assert_eq!(problems.len(), 5);
+        assert!(problems[0].starts_with("database_url is missing"));
+        assert!(problems[4].starts_with("unable to parse scoring config"));
+    }
+
+    #[test]
+    fn example_config_is_valid()
+    {
+        let file: ConfigFile = toml::from_str(include_str!("../toplist.example.toml")).unwrap();
+        assert!(Config::resolve(Args::default(), file).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_settings()
+    {
+        assert!(toml::from_str::<ConfigFile>("database = \"mysql://localhost/toplist\"").is_err());
+    }
+}
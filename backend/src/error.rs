@@ -0,0 +1,175 @@
+use crate::request_id;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use toplist_types::{ErrorCode, ErrorDetails, ErrorResponse, SteamIdError};
+
+#[derive(Debug)]
+pub enum ApiError
+{
+    BadRequest(String),
+    NotFound(&'static str),         //NOTE: What wasn't found, like "course" or "server"
+    Unauthorized,
+    RateLimited,                    //NOTE: The Steam API kept answering 429 after the Steam client's retries
+    Upstream(reqwest::Error),       //NOTE: The Steam API failed
+    Internal(String),               //NOTE: Cause of the error, only logged since it can have queries or paths in it
+}
+
+impl ApiError
+{
+    pub fn status(&self) -> StatusCode
+    {
+        match self
+        {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode
+    {
+        match self
+        {
+            ApiError::BadRequest(_) => ErrorCode::BadRequest,
+            ApiError::NotFound(_) => ErrorCode::NotFound,
+            ApiError::Unauthorized => ErrorCode::Unauthorized,
+            ApiError::RateLimited => ErrorCode::RateLimited,
+            ApiError::Upstream(_) => ErrorCode::Upstream,
+            ApiError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+
+    //NOTE: What the client gets to see
+    pub fn message(&self) -> String
+    {
+        match self
+        {
+            ApiError::BadRequest(msg) => msg.clone(),
+            ApiError::NotFound(resource) => format!("{} not found.", resource),
+            ApiError::Unauthorized => "Invalid API key.".to_string(),
+            ApiError::RateLimited => "Steam API is rate limiting the toplist, try again later.".to_string(),
+            ApiError::Upstream(_) => "Steam API error.".to_string(),
+            ApiError::Internal(_) => "Internal server error.".to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError
+{
+    //NOTE: Logged inside the request span, so the log line and the response share the request ID
+    fn into_response(self) -> Response
+    {
+        match &self
+        {
+            ApiError::Internal(cause) => tracing::error!("{}", cause),
+            ApiError::Upstream(e) => tracing::warn!("Steam API error: {}", e),
+            ApiError::RateLimited => tracing::warn!("Steam API is rate limiting"),
+            _ => tracing::debug!("{}", self.message()),
+        }
+
+        let body = ErrorResponse
+        {
+            error: ErrorDetails { code: self.code(), message: self.message(), request_id: request_id::current() },
+        };
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+//NOTE: RowNotFound only comes from fetch_one, repositories return an Option where a missing row is expected, so it's a bug
+//and ends up as an internal error like the rest
+impl From<sqlx::Error> for ApiError
+{
+    fn from(e: sqlx::Error) -> Self
+    {
+        ApiError::Internal(format!("Database error: {}", e))
+    }
+}
+
+impl From<JsonRejection> for ApiError
+{
+    fn from(e: JsonRejection) -> Self
+    {
+        ApiError::BadRequest(e.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError
+{
+    fn from(e: PathRejection) -> Self
+    {
+        ApiError::BadRequest(e.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError
+{
+    fn from(e: QueryRejection) -> Self
+    {
+        ApiError::BadRequest(e.body_text())
+    }
+}
+
+impl From<SteamIdError> for ApiError
+{
+    fn from(e: SteamIdError) -> Self
+    {
+        ApiError::BadRequest(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for ApiError
+{
+    fn from(e: reqwest::Error) -> Self
+    {
+        match e.status()
+        {
+            Some(StatusCode::TOO_MANY_REQUESTS) => ApiError::RateLimited,
+            _ => ApiError::Upstream(e),
+        }
+    }
+}
+
+impl From<argon2::password_hash::Error> for ApiError
+{
+    fn from(e: argon2::password_hash::Error) -> Self
+    {
+        ApiError::Internal(format!("Unable to hash API key: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    async fn respond(error: ApiError) -> (StatusCode, ErrorResponse)
+    {
+        let response = error.into_response();
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn error_responses()
+    {
+        let (status, body) = respond(ApiError::NotFound("course")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body.error.code, ErrorCode::NotFound);
+        assert_eq!(body.error.message, "course not found.");
+        assert_eq!(body.error.request_id, None);
+
+        //NOTE: The cause stays in the logs
+        let (status, body) = respond(ApiError::from(sqlx::Error::PoolTimedOut)).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body.error.code, ErrorCode::Internal);
+        assert_eq!(body.error.message, "Internal server error.");
+
+        assert!(matches!(ApiError::from(sqlx::Error::RowNotFound), ApiError::Internal(_)));
+    }
+}
@@ -0,0 +1,26 @@
+use crate::error::ApiError;
+use axum::extract::{FromRequest, FromRequestParts};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+//NOTE: axum's extractors with their rejections turned into ApiError, so a malformed body, path or query string gets the same
+//error body as every other error instead of axum's plain text one
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub struct Json<T>(pub T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct Path<T>(pub T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct Query<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Json<T>
+{
+    fn into_response(self) -> Response
+    {
+        axum::Json(self.0).into_response()
+    }
+}
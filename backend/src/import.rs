@@ -0,0 +1,717 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
+use std::fmt;
+use toplist_types::{CourseTime, SteamId};
+
+//NOTE: Column names the legacy toplist exports have used for each field, compared in lowercase
+const MAP_COLUMNS: [&str; 3] = ["map", "map_name", "mapname"];
+const COURSE_COLUMNS: [&str; 3] = ["course", "course_name", "coursename"];
+const REVERSE_COLUMNS: [&str; 2] = ["reverse", "rflag"];
+const STEAMID_COLUMNS: [&str; 4] = ["steamid", "steam_id", "auth", "authid"];
+const NAME_COLUMNS: [&str; 4] = ["player_name", "playername", "name", "nick"];
+const TIME_COLUMNS: [&str; 4] = ["time", "course_time", "runtime", "record_time"];
+const CHECKPOINTS_COLUMNS: [&str; 4] = ["checkpoints", "checkpoint", "cp", "cps"];
+const TELEPORTS_COLUMNS: [&str; 4] = ["teleports", "teleport_count", "tps", "gochecks"];
+const DATE_COLUMNS: [&str; 4] = ["date", "date_set", "timestamp", "created"];
+
+#[derive(Debug)]
+pub enum ImportError
+{
+    Io(std::io::Error),
+    Csv(csv::Error),
+    Sql(String),
+}
+
+impl fmt::Display for ImportError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            ImportError::Io(e) => write!(f, "unable to read the export: {}", e),
+            ImportError::Csv(e) => write!(f, "unable to parse the CSV export: {}", e),
+            ImportError::Sql(reason) => write!(f, "unable to parse the SQL dump: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+//NOTE: A row of the export with lowercase column names. line is the line of the CSV file or the statement of the SQL dump
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyRow
+{
+    pub line: usize,
+    pub fields: HashMap<String, String>,
+}
+
+impl LegacyRow
+{
+    fn get(&self, columns: &[&str]) -> Option<&str>
+    {
+        columns.iter().find_map(|column| self.fields.get(*column)).map(|value| value.trim()).filter(|value| !value.is_empty())
+    }
+}
+
+//NOTE: A legacy record normalised into the shape of the current schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run
+{
+    pub line: usize,
+    pub map_name: String,
+    pub course_name: String,
+    pub reverse: bool,
+    pub steamid: SteamId,
+    pub player_name: String,
+    pub course_time: CourseTime,
+    pub checkpoints: bool,
+    pub date_set: Option<NaiveDateTime>,
+}
+
+impl Run
+{
+    fn beats(&self, other: &Run) -> bool
+    {
+        if self.course_time == other.course_time
+        {
+            return self.date_set.is_some() && (other.date_set.is_none() || self.date_set < other.date_set);
+        }
+        self.beats_time(self.reverse, other.course_time)
+    }
+
+    //NOTE: Reverse courses are won by the slowest time. reverse is the course's flag in the database when it differs
+    pub fn beats_time(&self, reverse: bool, course_time: CourseTime) -> bool
+    {
+        if reverse { self.course_time > course_time } else { self.course_time < course_time }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report
+{
+    pub rows: usize,
+    pub duplicates: usize,
+    pub errors: Vec<String>,        //NOTE: Rows that couldn't be imported
+    pub conflicts: Vec<String>,     //NOTE: Rows that disagree with each other or the database, imported or skipped as noted
+}
+
+pub fn read_csv<R: std::io::Read>(reader: R) -> Result<Vec<LegacyRow>, ImportError>
+{
+    let mut reader = csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(reader);
+    let headers: Vec<String> = reader.headers().map_err(ImportError::Csv)?.iter().map(|header| header.to_lowercase()).collect();
+
+    let mut rows = Vec::new();
+    for record in reader.records()
+    {
+        let record = record.map_err(ImportError::Csv)?;
+        let line = record.position().map_or(0, |position| position.line() as usize);
+        rows.push(LegacyRow { line, fields: headers.iter().cloned().zip(record.iter().map(str::to_string)).collect() });
+    }
+
+    Ok(rows)
+}
+
+//NOTE: Reads the rows of every INSERT in a mysqldump style dump. Column names come from the INSERT or the table's CREATE TABLE.
+//Only rows of the given table are read when one is given
+pub fn read_sql_dump(contents: &str, table: Option<&str>) -> Result<Vec<LegacyRow>, ImportError>
+{
+    let mut columns: HashMap<String, Vec<String>> = HashMap::new();
+    let mut rows = Vec::new();
+
+    for (index, statement) in split_statements(contents).into_iter().enumerate()
+    {
+        let mut tokens = Tokens::new(&statement);
+        let first = tokens.keyword();
+        match first.as_deref()
+        {
+            Some("create") if tokens.keyword().as_deref() == Some("table") =>
+            {
+                let name = tokens.table_name().ok_or_else(|| ImportError::Sql(format!("statement {} has no table name", index + 1)))?;
+                columns.insert(name, tokens.column_definitions());
+            }
+            Some("insert") | Some("replace") =>
+            {
+                let name = loop
+                {
+                    match tokens.keyword().as_deref()
+                    {
+                        Some("into") => break tokens.table_name().ok_or_else(|| ImportError::Sql(format!("statement {} has no table name", index + 1)))?,
+                        Some(_) => continue,
+                        None => return Err(ImportError::Sql(format!("statement {} has no INTO", index + 1))),
+                    }
+                };
+                if table.is_some_and(|table| !table.eq_ignore_ascii_case(&name))
+                {
+                    continue;
+                }
+
+                let names = match tokens.column_list()
+                {
+                    Some(names) => names,
+                    None => columns.get(&name).cloned().ok_or_else(|| ImportError::Sql(format!("INSERT into {} without columns before its CREATE TABLE", name)))?,
+                };
+                if tokens.keyword().as_deref() != Some("values")
+                {
+                    return Err(ImportError::Sql(format!("statement {} is not an INSERT ... VALUES", index + 1)));
+                }
+                for values in tokens.tuples()
+                {
+                    if values.len() != names.len()
+                    {
+                        return Err(ImportError::Sql(format!("statement {} has {} values for {} columns", index + 1, values.len(), names.len())));
+                    }
+                    let fields = names.iter().cloned().zip(values.into_iter().map(|value| value.unwrap_or_default())).collect();
+                    rows.push(LegacyRow { line: index + 1, fields });
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    Ok(rows)
+}
+
+//NOTE: Splits on semicolons outside of strings, identifiers and comments. Comments are dropped
+fn split_statements(contents: &str) -> Vec<String>
+{
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next()
+    {
+        match c
+        {
+            '\'' | '"' | '`' =>
+            {
+                current.push(c);
+                while let Some(inner) = chars.next()
+                {
+                    current.push(inner);
+                    if inner == '\\' && c != '`'
+                    {
+                        if let Some(escaped) = chars.next()
+                        {
+                            current.push(escaped);
+                        }
+                    }
+                    else if inner == c
+                    {
+                        break;
+                    }
+                }
+            }
+            '-' | '#' if c == '#' || chars.peek() == Some(&'-') =>
+            {
+                for inner in chars.by_ref()
+                {
+                    if inner == '\n'
+                    {
+                        break;
+                    }
+                }
+                current.push('\n');
+            }
+            '/' if chars.peek() == Some(&'*') =>
+            {
+                chars.next();
+                let mut previous = ' ';
+                for inner in chars.by_ref()
+                {
+                    if previous == '*' && inner == '/'
+                    {
+                        break;
+                    }
+                    previous = inner;
+                }
+                current.push(' ');
+            }
+            ';' =>
+            {
+                if !current.trim().is_empty()
+                {
+                    statements.push(current.trim().to_string());
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty()
+    {
+        statements.push(current.trim().to_string());
+    }
+
+    statements
+}
+
+struct Tokens<'a>
+{
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Tokens<'a>
+{
+    fn new(statement: &'a str) -> Self
+    {
+        Tokens { chars: statement.chars().peekable() }
+    }
+
+    fn skip_whitespace(&mut self)
+    {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn next_is(&mut self, expected: char) -> bool
+    {
+        self.skip_whitespace();
+        self.chars.next_if_eq(&expected).is_some()
+    }
+
+    fn word(&mut self) -> Option<String>
+    {
+        self.skip_whitespace();
+        let mut word = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '$' || *c == '.' || *c == '-' || *c == '+')
+        {
+            word.push(c);
+        }
+        if word.is_empty() { None } else { Some(word) }
+    }
+
+    fn keyword(&mut self) -> Option<String>
+    {
+        self.word().map(|word| word.to_lowercase())
+    }
+
+    //NOTE: A plain or `quoted` identifier, only the table part of database.table is kept
+    fn identifier(&mut self) -> Option<String>
+    {
+        self.skip_whitespace();
+        let identifier = if self.chars.next_if_eq(&'`').is_some()
+        {
+            let mut identifier = String::new();
+            for c in self.chars.by_ref()
+            {
+                if c == '`'
+                {
+                    break;
+                }
+                identifier.push(c);
+            }
+            identifier
+        }
+        else
+        {
+            self.word()?
+        };
+        Some(identifier.to_lowercase())
+    }
+
+    fn table_name(&mut self) -> Option<String>
+    {
+        let mut name = self.identifier()?;
+        while self.next_is('.')
+        {
+            name = self.identifier()?;
+        }
+        //NOTE: Unquoted database.table is read as a single word
+        Some(name.rsplit('.').next().unwrap_or_default().to_string())
+    }
+
+    fn column_list(&mut self) -> Option<Vec<String>>
+    {
+        if !self.next_is('(')
+        {
+            return None;
+        }
+        let mut names = Vec::new();
+        loop
+        {
+            names.push(self.identifier()?);
+            if !self.next_is(',')
+            {
+                break;
+            }
+        }
+        self.next_is(')');
+        Some(names)
+    }
+
+    //NOTE: Column names of a CREATE TABLE, skipping keys, indexes and constraints
+    fn column_definitions(&mut self) -> Vec<String>
+    {
+        let mut names = Vec::new();
+        if !self.next_is('(')
+        {
+            return names;
+        }
+        let mut depth = 1;
+        let mut start_of_definition = true;
+        while depth > 0
+        {
+            self.skip_whitespace();
+            if start_of_definition
+            {
+                start_of_definition = false;
+                let quoted = self.chars.peek() == Some(&'`');
+                if let Some(name) = self.identifier()
+                {
+                    let keyword = ["primary", "key", "unique", "index", "constraint", "fulltext", "spatial", "foreign", "check"].contains(&name.as_str());
+                    if quoted || !keyword
+                    {
+                        names.push(name);
+                    }
+                }
+                continue;
+            }
+            match self.chars.next()
+            {
+                Some('(') => depth += 1,
+                Some(')') => depth -= 1,
+                Some(',') if depth == 1 => start_of_definition = true,
+                Some(quote @ ('\'' | '"')) =>
+                {
+                    self.string(quote);
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+        names
+    }
+
+    fn string(&mut self, quote: char) -> String
+    {
+        let mut value = String::new();
+        while let Some(c) = self.chars.next()
+        {
+            match c
+            {
+                '\\' => match self.chars.next()
+                {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('0') => value.push('\0'),
+                    Some(escaped) => value.push(escaped),
+                    None => break,
+                },
+                c if c == quote =>
+                {
+                    //NOTE: A doubled quote is an escaped quote
+                    if self.chars.next_if_eq(&quote).is_some()
+                    {
+                        value.push(quote);
+                    }
+                    else
+                    {
+                        break;
+                    }
+                }
+                c => value.push(c),
+            }
+        }
+        value
+    }
+
+    //NOTE: The (...), (...) list after VALUES. NULL is read as None
+    fn tuples(&mut self) -> Vec<Vec<Option<String>>>
+    {
+        let mut tuples = Vec::new();
+        while self.next_is('(')
+        {
+            let mut values = Vec::new();
+            loop
+            {
+                self.skip_whitespace();
+                let value = match self.chars.peek()
+                {
+                    Some(&quote) if quote == '\'' || quote == '"' =>
+                    {
+                        self.chars.next();
+                        Some(self.string(quote))
+                    }
+                    _ => self.word().filter(|word| !word.eq_ignore_ascii_case("null")),
+                };
+                values.push(value);
+                if !self.next_is(',')
+                {
+                    break;
+                }
+            }
+            self.next_is(')');
+            tuples.push(values);
+            if !self.next_is(',')
+            {
+                break;
+            }
+        }
+        tuples
+    }
+}
+
+fn parse_flag(value: &str) -> Option<bool>
+{
+    match value.to_lowercase().as_str()
+    {
+        "1" | "true" | "yes" | "y" | "t" => Some(true),
+        "0" | "false" | "no" | "n" | "f" => Some(false),
+        _ => None,
+    }
+}
+
+//NOTE: Legacy dates are either unix timestamps or MySQL style dates, with or without the time
+fn parse_date(value: &str) -> Option<NaiveDateTime>
+{
+    if let Ok(timestamp) = value.parse::<i64>()
+    {
+        return DateTime::from_timestamp(timestamp, 0).map(|date| date.naive_utc());
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))
+        .or_else(|| NaiveDate::parse_from_str(value, "%d.%m.%Y").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))
+}
+
+pub fn normalise(row: &LegacyRow) -> Result<Run, String>
+{
+    let required = |columns: &[&str], field: &str| row.get(columns).ok_or_else(|| format!("line {}: missing {}", row.line, field));
+
+    let map_name = required(&MAP_COLUMNS, "map")?.to_string();
+    let course_name = required(&COURSE_COLUMNS, "course")?.to_string();
+    let steamid = required(&STEAMID_COLUMNS, "steamid")?;
+    let steamid = steamid.parse::<SteamId>().map_err(|e| format!("line {}: {}", row.line, e))?;
+    let time = required(&TIME_COLUMNS, "time")?;
+    let course_time = time.parse::<CourseTime>().map_err(|e| format!("line {}: {}", row.line, e))?;
+
+    let reverse = match row.get(&REVERSE_COLUMNS)
+    {
+        Some(reverse) => parse_flag(reverse).ok_or_else(|| format!("line {}: '{}' is not a valid reverse flag", row.line, reverse))?,
+        None => false,
+    };
+    //NOTE: Exports without a checkpoint flag count runs with teleports as checkpoint runs
+    let checkpoints = match (row.get(&CHECKPOINTS_COLUMNS), row.get(&TELEPORTS_COLUMNS))
+    {
+        (Some(checkpoints), _) => parse_flag(checkpoints).ok_or_else(|| format!("line {}: '{}' is not a valid checkpoint flag", row.line, checkpoints))?,
+        (None, Some(teleports)) => teleports.parse::<u32>().map_err(|_| format!("line {}: '{}' is not a valid teleport count", row.line, teleports))? > 0,
+        (None, None) => false,
+    };
+    let date_set = match row.get(&DATE_COLUMNS)
+    {
+        Some(date) => Some(parse_date(date).ok_or_else(|| format!("line {}: '{}' is not a valid date", row.line, date))?),
+        None => None,
+    };
+    let player_name = row.get(&NAME_COLUMNS).map_or_else(|| steamid.steamid2(), str::to_string);
+
+    Ok(Run { line: row.line, map_name, course_name, reverse, steamid, player_name, course_time, checkpoints, date_set })
+}
+
+//NOTE: Keeps the best run of each player on each course and record type. Courses with disagreeing reverse flags and
+//players with several names are reported, the first reverse flag and the most recent name win
+pub fn dedupe(runs: Vec<Run>, report: &mut Report) -> Vec<Run>
+{
+    let mut reverse: HashMap<(String, String), bool> = HashMap::new();
+    let mut names: HashMap<SteamId, (Option<NaiveDateTime>, String)> = HashMap::new();
+    let mut best: HashMap<(String, String, bool, SteamId), Run> = HashMap::new();
+    let mut order: Vec<(String, String, bool, SteamId)> = Vec::new();
+
+    for mut run in runs
+    {
+        let course = (run.map_name.clone(), run.course_name.clone());
+        let course_reverse = *reverse.entry(course).or_insert(run.reverse);
+        if course_reverse != run.reverse
+        {
+            report.conflicts.push(format!("line {}: {} {} was already read as {}reverse", run.line, run.map_name, run.course_name, if course_reverse { "" } else { "not " }));
+            run.reverse = course_reverse;
+        }
+
+        match names.get_mut(&run.steamid)
+        {
+            Some((date, name)) if *name != run.player_name =>
+            {
+                report.conflicts.push(format!("line {}: {} is named both '{}' and '{}'", run.line, run.steamid.steamid2(), name, run.player_name));
+                if run.date_set > *date
+                {
+                    *date = run.date_set;
+                    *name = run.player_name.clone();
+                }
+            }
+            Some(_) => {}
+            None =>
+            {
+                names.insert(run.steamid, (run.date_set, run.player_name.clone()));
+            }
+        }
+
+        let key = (run.map_name.clone(), run.course_name.clone(), run.checkpoints, run.steamid);
+        match best.get_mut(&key)
+        {
+            Some(current) =>
+            {
+                report.duplicates += 1;
+                if run.beats(current)
+                {
+                    *current = run;
+                }
+            }
+            None =>
+            {
+                order.push(key.clone());
+                best.insert(key, run);
+            }
+        }
+    }
+
+    order.into_iter().filter_map(|key| best.remove(&key)).map(|mut run|
+    {
+        if let Some((_, name)) = names.get(&run.steamid)
+        {
+            run.player_name = name.clone();
+        }
+        run
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const DUMP: &str = r#"
+-- MySQL dump 10.13
+/*!40101 SET NAMES utf8 */;
+DROP TABLE IF EXISTS `kz_records`;
+CREATE TABLE `kz_records` (
+  `id` int(11) NOT NULL AUTO_INCREMENT,
+  `map` varchar(64) NOT NULL,
+  `course` varchar(64) NOT NULL DEFAULT 'Main',
+  `steamid` varchar(32) NOT NULL,
+  `name` varchar(64) NOT NULL,
+  `time` decimal(10,3) NOT NULL,
+  `cp` tinyint(1) NOT NULL,
+  `date` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `map` (`map`,`course`)
+) ENGINE=MyISAM DEFAULT CHARSET=utf8;
+INSERT INTO `kz_records` VALUES (1,'kz_ethmo','Main','STEAM_0:1:1234','O\'Neil; the 1st',83.450,0,'2009-05-01 20:00:00'),(2,'kz_ethmo','Main','STEAM_0:0:42','Menko',80.100,1,'2009-05-02 20:00:00');
+INSERT INTO kz_other (a) VALUES (NULL);
+INSERT INTO `kz_records` (`map`, `course`, `steamid`, `name`, `time`, `cp`, `date`) VALUES ('kz_ethmo', 'Main', '[U:1:84]', 'Menko', '01:19.00', 1, '2009-05-03');
+"#;
+
+    fn row(fields: &[(&str, &str)]) -> LegacyRow
+    {
+        LegacyRow { line: 1, fields: fields.iter().map(|(column, value)| (column.to_string(), value.to_string())).collect() }
+    }
+
+    fn run(steamid: &str, name: &str, time: &str, date: &str) -> Run
+    {
+        normalise(&row(&[("map", "kz_ethmo"), ("course", "Main"), ("steamid", steamid), ("name", name), ("time", time), ("date", date)])).unwrap()
+    }
+
+    #[test]
+    fn reads_sql_dump()
+    {
+        let rows = read_sql_dump(DUMP, Some("kz_records")).unwrap();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].fields["name"], "O'Neil; the 1st");
+        assert_eq!(rows[0].fields["time"], "83.450");
+        assert_eq!(rows[1].fields["steamid"], "STEAM_0:0:42");
+        assert_eq!(rows[2].fields["steamid"], "[U:1:84]");
+        assert_eq!(rows[2].fields.len(), 7);
+    }
+
+    #[test]
+    fn reads_every_table_without_a_filter()
+    {
+        let rows = read_sql_dump(DUMP, None).unwrap();
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[2].fields["a"], "");
+    }
+
+    #[test]
+    fn rejects_inserts_without_columns()
+    {
+        assert!(read_sql_dump("INSERT INTO records VALUES (1, 2);", None).is_err());
+        assert!(read_sql_dump("INSERT INTO records (a, b) VALUES (1);", None).is_err());
+    }
+
+    #[test]
+    fn reads_csv()
+    {
+        let csv = "Map,Course,SteamID,Name,Time,Teleports,Date\nkz_ethmo,Main,STEAM_0:1:1234,\"Doe, John\",1:23.45,3,1241208000\n";
+        let rows = read_csv(csv.as_bytes()).unwrap();
+        let run = normalise(&rows[0]).unwrap();
+
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(run.player_name, "Doe, John");
+        assert_eq!(run.course_time, CourseTime::from_millis(83_450));
+        assert!(run.checkpoints);
+        assert_eq!(run.date_set.unwrap().to_string(), "2009-05-01 20:00:00");
+    }
+
+    #[test]
+    fn normalises_dump_rows()
+    {
+        let runs: Vec<Run> = read_sql_dump(DUMP, Some("kz_records")).unwrap().iter().map(|row| normalise(row).unwrap()).collect();
+
+        assert_eq!(runs[0].course_time, CourseTime::from_millis(83_450));
+        assert!(!runs[0].checkpoints);
+        assert_eq!(runs[1].steamid, runs[2].steamid);
+        assert_eq!(runs[2].course_time, CourseTime::from_millis(79_000));
+        assert_eq!(runs[2].date_set.unwrap().to_string(), "2009-05-03 00:00:00");
+    }
+
+    #[test]
+    fn reports_invalid_rows()
+    {
+        assert!(normalise(&row(&[("map", "kz_ethmo"), ("course", "Main"), ("steamid", "STEAM_0:1:1"), ("time", "later")])).is_err());
+        assert!(normalise(&row(&[("map", "kz_ethmo"), ("course", "Main"), ("steamid", "someone"), ("time", "10.0")])).is_err());
+        assert!(normalise(&row(&[("map", "kz_ethmo"), ("steamid", "STEAM_0:1:1"), ("time", "10.0")])).is_err());
+        assert!(normalise(&row(&[("map", "kz_ethmo"), ("course", "Main"), ("steamid", "STEAM_0:1:1"), ("time", "10.0"), ("cp", "maybe")])).is_err());
+    }
+
+    #[test]
+    fn keeps_best_run_and_latest_name()
+    {
+        let mut report = Report::default();
+        let runs = dedupe(vec![
+            run("STEAM_0:1:1", "old name", "00:01:30.00", "2009-01-01"),
+            run("STEAM_0:1:1", "new name", "00:01:20.00", "2010-01-01"),
+            run("STEAM_0:1:1", "new name", "00:01:25.00", "2011-01-01"),
+            run("STEAM_0:0:2", "other", "00:01:40.00", "2009-01-01"),
+        ], &mut report);
+
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].course_time, CourseTime::from_millis(80_000));
+        assert_eq!(runs[0].player_name, "new name");
+        assert_eq!(report.duplicates, 2);
+        assert_eq!(report.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn ties_keep_the_earliest_run()
+    {
+        let mut report = Report::default();
+        let runs = dedupe(vec![
+            run("STEAM_0:1:1", "player", "00:01:30.00", "2010-01-01"),
+            run("STEAM_0:1:1", "player", "00:01:30.00", "2009-01-01"),
+        ], &mut report);
+
+        assert_eq!(runs[0].date_set.unwrap().to_string(), "2009-01-01 00:00:00");
+    }
+
+    #[test]
+    fn reverse_courses_keep_the_longest_run()
+    {
+        let mut report = Report::default();
+        let mut slow = run("STEAM_0:1:1", "player", "00:02:00.00", "2009-01-01");
+        let mut fast = run("STEAM_0:1:1", "player", "00:01:00.00", "2009-01-02");
+        slow.reverse = true;
+        fast.reverse = true;
+
+        assert_eq!(dedupe(vec![fast, slow], &mut report)[0].course_time, CourseTime::from_millis(120_000));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::scoring::ScoringConfig;
+use std::collections::HashMap;
+use toplist_types::{CountryEntry, LeaderboardEntry, SteamId};
+
+//NOTE: A single record along with where it places on its course and the tier of the course's map
+#[derive(sqlx::FromRow)]
+pub struct Placement
+{
+    pub steamid: SteamId,
+    pub player_name: String,
+    pub avatar: Option<String>,
+    pub country: Option<String>,
+    pub rank: u64,
+    pub field_size: u64,
+    pub tier: Option<u8>,
+}
+
+//NOTE: Players are ordered by points, then WRs, top 10 placements and completed courses. Players with identical stats share a rank
+pub fn rank_players(placements: Vec<Placement>, scoring: &ScoringConfig) -> Vec<LeaderboardEntry>
+{
+    let mut players: HashMap<SteamId, LeaderboardEntry> = HashMap::new();
+
+    for placement in placements
+    {
+        let entry = players.entry(placement.steamid).or_insert_with(|| LeaderboardEntry
+        {
+            rank: 0,
+            steamid: placement.steamid,
+            player_name: placement.player_name,
+            avatar: placement.avatar,
+            country: placement.country,
+            points: 0,
+            wrs: 0,
+            top10: 0,
+            courses: 0,
+        });
+
+        entry.points += scoring.points(placement.rank, placement.field_size, placement.tier);
+        entry.wrs += (placement.rank == 1) as u32;
+        entry.top10 += (placement.rank <= 10) as u32;
+        entry.courses += 1;
+    }
+
+    let mut leaderboard: Vec<LeaderboardEntry> = players.into_values().collect();
+    leaderboard.sort_by(|a, b| sort_key(b).cmp(&sort_key(a)).then_with(|| a.player_name.cmp(&b.player_name)));
+
+    for index in 0..leaderboard.len()
+    {
+        leaderboard[index].rank = if index > 0 && sort_key(&leaderboard[index]) == sort_key(&leaderboard[index - 1])
+        {
+            leaderboard[index - 1].rank
+        }
+        else
+        {
+            index + 1
+        };
+    }
+
+    leaderboard
+}
+
+fn sort_key(entry: &LeaderboardEntry) -> (u64, u32, u32, u32)
+{
+    (entry.points, entry.wrs, entry.top10, entry.courses)
+}
+
+//NOTE: Adds up the ranked players of each country, ordered by points and then WRs. Players without a country on their
+//Steam profile are left out
+pub fn rank_countries(players: &[LeaderboardEntry]) -> Vec<CountryEntry>
+{
+    let mut countries: HashMap<&str, CountryEntry> = HashMap::new();
+
+    for (country, player) in players.iter().filter_map(|player| Some((player.country.as_deref()?, player)))
+    {
+        let entry = countries.entry(country).or_insert_with(|| CountryEntry
+        {
+            rank: 0,
+            country: country.to_string(),
+            players: 0,
+            points: 0,
+            wrs: 0,
+            top10: 0,
+        });
+
+        entry.players += 1;
+        entry.points += player.points;
+        entry.wrs += player.wrs;
+        entry.top10 += player.top10;
+    }
+
+    let country_key = |entry: &CountryEntry| (entry.points, entry.wrs, entry.top10);
+    let mut ranking: Vec<CountryEntry> = countries.into_values().collect();
+    ranking.sort_by(|a, b| country_key(b).cmp(&country_key(a)).then_with(|| a.country.cmp(&b.country)));
+
+    for index in 0..ranking.len()
+    {
+        ranking[index].rank = if index > 0 && country_key(&ranking[index]) == country_key(&ranking[index - 1])
+        {
+            ranking[index - 1].rank
+        }
+        else
+        {
+            index + 1
+        };
+    }
+
+    ranking
+}
@@ -0,0 +1,14 @@
+pub mod api;
+pub mod config;
+pub mod error;
+pub mod extract;
+pub mod import;
+pub mod leaderboard;
+pub mod models;
+pub mod profiles;
+pub mod repository;
+pub mod request_id;
+pub mod scoring;
+pub mod servers;
+pub mod steam;
+pub mod wr_history;
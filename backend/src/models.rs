@@ -0,0 +1,65 @@
+use toplist_types::{CourseTime, SteamId};
+
+//NOTE: Rows returned by the repositories, shared by every database backend. Rows that are served as they are, like
+//Record and Map, live in toplist-types
+
+#[derive(sqlx::FromRow)]
+pub struct RecordsSummary
+{
+    pub records_count: i64,
+    pub wr_time: Option<CourseTime>,
+}
+
+//NOTE: Player record along with its placement on the course, as returned by the database
+#[derive(sqlx::FromRow)]
+pub struct PlayerRecordRow
+{
+    pub course_id: i32,
+    pub map_name: String,
+    pub course_name: String,
+    pub course_time: CourseTime,
+    pub date: Option<String>,
+    pub rank: u64,
+    pub field_size: u64,
+    pub wr_time: CourseTime,
+    pub tier: Option<u8>,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct RecentRecordRow
+{
+    pub map_name: String,
+    pub course_id: i32,
+    pub course_name: String,
+    pub player_name: String,
+    pub steamid: SteamId,
+    pub course_time: CourseTime,
+    pub date: Option<String>,
+    pub wr: u64,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct HistoryRow
+{
+    pub course_time: CourseTime,
+    pub date: Option<String>,
+    pub timestamp: i64,
+}
+
+//NOTE: What the profile refresher keeps of a player's Steam profile
+pub struct PlayerProfile
+{
+    pub steamid: SteamId,
+    pub personaname: String,
+    pub avatar: String,
+    pub avatarmedium: String,
+    pub avatarfull: String,
+    pub loccountrycode: Option<String>,
+}
+
+//NOTE: Outcome of a submitted run, rank is where the submitted time places on the course
+pub struct SubmittedRecord
+{
+    pub improved: bool,
+    pub rank: u64,
+}
@@ -0,0 +1,140 @@
+use crate::models::PlayerProfile;
+use crate::repository::ToplistRepository;
+use crate::steam::{Player, SteamClient};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+//NOTE: Profiles are stored after every batch of 100, the most GetPlayerSummaries takes, so a failure halfway keeps what was
+//already looked up. Whatever is left over is picked up on the next refresh
+const BATCH_SIZE: usize = 100;
+const MAX_PROFILES_PER_REFRESH: u32 = 5000;
+
+#[derive(Debug)]
+pub enum RefreshError
+{
+    Database(sqlx::Error),
+    Steam(reqwest::Error),
+}
+
+impl fmt::Display for RefreshError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            RefreshError::Database(e) => write!(f, "database error: {}", e),
+            RefreshError::Steam(e) => write!(f, "Steam API error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RefreshError {}
+
+impl From<sqlx::Error> for RefreshError
+{
+    fn from(e: sqlx::Error) -> Self
+    {
+        RefreshError::Database(e)
+    }
+}
+
+impl From<reqwest::Error> for RefreshError
+{
+    fn from(e: reqwest::Error) -> Self
+    {
+        RefreshError::Steam(e)
+    }
+}
+
+fn profile(player: Player) -> Option<PlayerProfile>
+{
+    Some(PlayerProfile
+    {
+        steamid: player.steamid.parse().ok()?,
+        personaname: player.personaname,
+        avatar: player.avatar,
+        avatarmedium: player.avatarmedium,
+        avatarfull: player.avatarfull,
+        loccountrycode: player.loccountrycode.filter(|code| !code.is_empty()),
+    })
+}
+
+//NOTE: Looks up every player whose profile is older than max_age, returns how many players were looked up
+pub async fn refresh_stale_profiles(repository: &dyn ToplistRepository, steam: &dyn SteamClient, max_age: Duration, now: i64) -> Result<usize, RefreshError>
+{
+    let refreshed_before = now - max_age.as_secs() as i64;
+    let steamids = repository.stale_players(refreshed_before, MAX_PROFILES_PER_REFRESH).await?;
+
+    for batch in steamids.chunks(BATCH_SIZE)
+    {
+        let profiles: Vec<PlayerProfile> = steam.player_summaries(batch).await?.into_iter().filter_map(profile).collect();
+        repository.update_player_profiles(batch, &profiles).await?;
+    }
+
+    Ok(steamids.len())
+}
+
+//NOTE: Refreshes stale profiles every interval until the server shuts down. Failures are logged and retried on the next tick
+pub fn spawn_refresher(repository: Arc<dyn ToplistRepository>, steam: Arc<dyn SteamClient>, interval: Duration, max_age: Duration) -> tokio::task::JoinHandle<()>
+{
+    tokio::spawn(async move
+    {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop
+        {
+            ticks.tick().await;
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|now| now.as_secs() as i64).unwrap_or_default();
+            match refresh_stale_profiles(repository.as_ref(), steam.as_ref(), max_age, now).await
+            {
+                Ok(0) => {}
+                Ok(refreshed) => tracing::info!("Refreshed the Steam profiles of {} players", refreshed),
+                Err(e) => tracing::warn!("Unable to refresh Steam profiles: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::repository::memory::InMemoryRepository;
+    use crate::steam::FakeSteamClient;
+    use toplist_types::{RecordSort, SteamId};
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn player(account_id: u32) -> SteamId
+    {
+        SteamId::from_account_id(1, account_id).unwrap()
+    }
+
+    #[tokio::test]
+    async fn stores_profiles_of_stale_players()
+    {
+        let repository = InMemoryRepository::new();
+        repository.add_map("kz_test", None);
+        let course_id = repository.add_course("kz_test", "main", false);
+        for account_id in 1..=150
+        {
+            repository.add_record(course_id, player(account_id), &format!("player {}", account_id), "00:01:00.000".parse().unwrap(), false, 0);
+        }
+        let steam = FakeSteamClient::new();
+        steam.add_player(player(1), "steam 1", Some("FI"));
+        steam.add_player(player(2), "steam 2", Some(""));
+
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64;
+        let refreshed = refresh_stale_profiles(&repository, &steam, Duration::from_secs(DAY), now).await.unwrap();
+        assert_eq!((refreshed, steam.requests()), (150, 2));
+
+        let course = repository.course(course_id).await.unwrap().unwrap();
+        let records = repository.records(&course, false, RecordSort::Player, 2, 0).await.unwrap();
+        assert_eq!(records.iter().map(|record| record.country.clone()).collect::<Vec<Option<String>>>(), [Some("FI".to_string()), None]);
+        assert!(records[0].avatar.is_some());
+
+        assert_eq!(refresh_stale_profiles(&repository, &steam, Duration::from_secs(DAY), now).await.unwrap(), 0);
+        assert_eq!(refresh_stale_profiles(&repository, &steam, Duration::from_secs(DAY), now + 2 * DAY as i64).await.unwrap(), 150);
+    }
+}
@@ -0,0 +1,502 @@
+use super::ToplistRepository;
+use crate::leaderboard::Placement;
+use crate::models::{HistoryRow, PlayerProfile, PlayerRecordRow, RecentRecordRow, RecordsSummary, SubmittedRecord};
+use crate::servers::{Server, ServerKey};
+use crate::wr_history::HistoryRecord;
+use axum::async_trait;
+use chrono::DateTime;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use toplist_types::{Course, CourseName, CourseTime, Map, PurgedRecords, Record, RecordSort, ServerInfo, SteamId};
+
+struct StoredMap
+{
+    map_name: String,
+    times_played: i32,
+    time_added: i64,
+    tier: Option<u8>,
+}
+
+//NOTE: A row of record or record_cp, also used for the rows of record_history
+#[derive(Clone)]
+struct StoredRecord
+{
+    key: u64,
+    steamid: SteamId,
+    course_id: i32,
+    checkpoints: bool,
+    course_time: CourseTime,
+    date_set: i64,
+    server_id: Option<i32>,
+}
+
+#[derive(Default)]
+struct StoredPlayer
+{
+    player_name: String,
+    avatar: Option<String>,
+    country: Option<String>,
+    profile_updated_at: Option<i64>,
+}
+
+struct StoredServer
+{
+    server_id: i32,
+    name: String,
+    key_hash: Option<String>,
+    created_at: i64,
+    key_rotated_at: Option<i64>,
+    revoked_at: Option<i64>,
+}
+
+#[derive(Default)]
+struct Store
+{
+    maps: Vec<StoredMap>,
+    courses: Vec<Course>,
+    players: HashMap<SteamId, StoredPlayer>,
+    records: Vec<StoredRecord>,
+    history: Vec<StoredRecord>,
+    servers: Vec<StoredServer>,
+    next_key: u64,
+}
+
+impl Store
+{
+    fn next_key(&mut self) -> u64
+    {
+        self.next_key += 1;
+        self.next_key
+    }
+
+    fn player_name(&self, steamid: SteamId) -> String
+    {
+        self.players.get(&steamid).map(|player| player.player_name.clone()).unwrap_or_default()
+    }
+
+    fn avatar(&self, steamid: SteamId) -> Option<String>
+    {
+        self.players.get(&steamid)?.avatar.clone()
+    }
+
+    fn country(&self, steamid: SteamId) -> Option<String>
+    {
+        self.players.get(&steamid)?.country.clone()
+    }
+
+    fn reverse(&self, course_id: i32) -> bool
+    {
+        self.courses.iter().any(|course| course.course_id == course_id && course.reverse != 0)
+    }
+
+    fn field(&self, course_id: i32, checkpoints: bool) -> impl Iterator<Item = &StoredRecord>
+    {
+        self.records.iter().filter(move |record| record.course_id == course_id && record.checkpoints == checkpoints)
+    }
+
+    //NOTE: Same as RANK() in the SQL queries, one more than the number of strictly better times on the course
+    fn rank(&self, course_id: i32, checkpoints: bool, course_time: CourseTime) -> u64
+    {
+        let reverse = self.reverse(course_id);
+        self.field(course_id, checkpoints).filter(|record| beats(reverse, record.course_time, course_time)).count() as u64 + 1
+    }
+
+    fn wr_time(&self, course_id: i32, checkpoints: bool) -> Option<CourseTime>
+    {
+        let times = self.field(course_id, checkpoints).map(|record| record.course_time);
+        if self.reverse(course_id) { times.max() } else { times.min() }
+    }
+
+    fn tier(&self, course_id: i32) -> Option<u8>
+    {
+        let course = self.courses.iter().find(|course| course.course_id == course_id)?;
+        self.maps.iter().find(|map| map.map_name == course.mapfk)?.tier
+    }
+
+    //NOTE: Writes the personal best the way an INSERT or UPDATE of record and record_cp does, the history is kept like the triggers do
+    fn set_record(&mut self, steamid: SteamId, course_id: i32, checkpoints: bool, course_time: CourseTime, date_set: i64, server_id: Option<i32>)
+    {
+        let key = self.next_key();
+        let record = StoredRecord { key, steamid, course_id, checkpoints, course_time, date_set, server_id };
+        match self.records.iter_mut().find(|stored| stored.steamid == steamid && stored.course_id == course_id && stored.checkpoints == checkpoints)
+        {
+            Some(stored) if stored.course_time == course_time =>
+            {
+                stored.date_set = date_set;
+                stored.server_id = server_id;
+                return;
+            }
+            Some(stored) =>
+            {
+                stored.course_time = course_time;
+                stored.date_set = date_set;
+                stored.server_id = server_id;
+            }
+            None => self.records.push(record.clone()),
+        }
+        let history_key = self.next_key();
+        self.history.push(StoredRecord { key: history_key, ..record });
+    }
+}
+
+//NOTE: Reverse courses are won by the longest time
+fn beats(reverse: bool, time: CourseTime, other: CourseTime) -> bool
+{
+    if reverse { time > other } else { time < other }
+}
+
+fn format_timestamp(timestamp: i64, format: &str) -> Option<String>
+{
+    DateTime::from_timestamp(timestamp, 0).map(|date| date.format(format).to_string())
+}
+
+fn now() -> i64
+{
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|now| now.as_secs() as i64).unwrap_or_default()
+}
+
+//NOTE: Keeps everything in memory so handlers can be tested without a database. Fixtures are added with the add_ functions,
+//timestamps are Unix timestamps
+#[derive(Default)]
+pub struct InMemoryRepository
+{
+    store: Mutex<Store>,
+}
+
+impl InMemoryRepository
+{
+    pub fn new() -> Self
+    {
+        InMemoryRepository::default()
+    }
+
+    pub fn add_map(&self, map_name: &str, tier: Option<u8>)
+    {
+        self.store.lock().unwrap().maps.push(StoredMap { map_name: map_name.to_string(), times_played: 0, time_added: now(), tier });
+    }
+
+    pub fn add_course(&self, map_name: &str, course_name: &str, reverse: bool) -> i32
+    {
+        let mut store = self.store.lock().unwrap();
+        let course_id = store.courses.iter().map(|course| course.course_id).max().unwrap_or_default() + 1;
+        store.courses.push(Course { course_id, course_name: course_name.to_string(), mapfk: map_name.to_string(), reverse: reverse as i8 });
+        course_id
+    }
+
+    //NOTE: Replaces the player's personal best whether or not the time is better, like the importer writing to the tables directly
+    pub fn add_record(&self, course_id: i32, steamid: SteamId, player_name: &str, course_time: CourseTime, checkpoints: bool, date_set: i64)
+    {
+        let mut store = self.store.lock().unwrap();
+        store.players.entry(steamid).or_default().player_name = player_name.to_string();
+        store.set_record(steamid, course_id, checkpoints, course_time, date_set, None);
+    }
+
+    //NOTE: Stores a player's Steam profile the way the profile refresher does
+    pub fn add_profile(&self, steamid: SteamId, avatar: &str, country: Option<&str>)
+    {
+        let mut store = self.store.lock().unwrap();
+        let player = store.players.entry(steamid).or_default();
+        player.avatar = Some(avatar.to_string());
+        player.country = country.map(str::to_string);
+        player.profile_updated_at = Some(now());
+    }
+
+    pub fn add_server(&self, name: &str, key_hash: &str) -> i32
+    {
+        let mut store = self.store.lock().unwrap();
+        let server_id = store.servers.iter().map(|server| server.server_id).max().unwrap_or_default() + 1;
+        store.servers.push(StoredServer { server_id, name: name.to_string(), key_hash: Some(key_hash.to_string()), created_at: now(), key_rotated_at: None, revoked_at: None });
+        server_id
+    }
+}
+
+#[async_trait]
+impl ToplistRepository for InMemoryRepository
+{
+    async fn maps(&self) -> Result<Vec<Map>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        Ok(store.maps.iter().map(|map| Map
+        {
+            map_name: map.map_name.clone(),
+            times_played: Some(map.times_played),
+            time_added: format_timestamp(map.time_added, "%Y-%m-%d"),
+        }).collect())
+    }
+
+    async fn course_names(&self, map_name: &str) -> Result<Vec<CourseName>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        Ok(store.courses.iter().filter(|course| course.mapfk == map_name).map(|course| CourseName
+        {
+            course_id: course.course_id,
+            course_name: course.course_name.clone(),
+        }).collect())
+    }
+
+    async fn course(&self, course_id: i32) -> Result<Option<Course>, sqlx::Error>
+    {
+        Ok(self.store.lock().unwrap().courses.iter().find(|course| course.course_id == course_id).cloned())
+    }
+
+    async fn course_by_name(&self, map_name: &str, course_name: &str) -> Result<Option<Course>, sqlx::Error>
+    {
+        Ok(self.store.lock().unwrap().courses.iter().find(|course| course.mapfk == map_name && course.course_name == course_name).cloned())
+    }
+
+    async fn records_summary(&self, course: &Course, checkpoints: bool) -> Result<RecordsSummary, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        Ok(RecordsSummary
+        {
+            records_count: store.field(course.course_id, checkpoints).count() as i64,
+            wr_time: store.wr_time(course.course_id, checkpoints),
+        })
+    }
+
+    async fn records(&self, course: &Course, checkpoints: bool, sort: RecordSort, limit: u32, offset: u32) -> Result<Vec<Record>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        let reverse = course.reverse != 0;
+        let by_time = |a: &StoredRecord, b: &StoredRecord| if reverse { b.course_time.cmp(&a.course_time) } else { a.course_time.cmp(&b.course_time) };
+
+        let mut field: Vec<&StoredRecord> = store.field(course.course_id, checkpoints).collect();
+        field.sort_by(|a, b|
+        {
+            let order = match sort
+            {
+                RecordSort::Date => b.date_set.cmp(&a.date_set),
+                RecordSort::Player => store.player_name(a.steamid).cmp(&store.player_name(b.steamid)),
+                RecordSort::Time => Ordering::Equal,
+            };
+            order.then_with(|| by_time(a, b)).then(a.date_set.cmp(&b.date_set)).then(a.key.cmp(&b.key))
+        });
+
+        Ok(field.into_iter().skip(offset as usize).take(limit as usize).map(|record| Record
+        {
+            rank: store.rank(record.course_id, checkpoints, record.course_time),
+            player_name: store.player_name(record.steamid),
+            steamid: record.steamid,
+            course_time: record.course_time,
+            date: format_timestamp(record.date_set, "%Y-%m-%d"),
+            diff: String::new(),
+            avatar: store.avatar(record.steamid),
+            country: store.country(record.steamid),
+        }).collect())
+    }
+
+    async fn placements(&self, checkpoints: bool) -> Result<Vec<Placement>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        Ok(store.records.iter().filter(|record| record.checkpoints == checkpoints).map(|record| Placement
+        {
+            steamid: record.steamid,
+            player_name: store.player_name(record.steamid),
+            avatar: store.avatar(record.steamid),
+            country: store.country(record.steamid),
+            rank: store.rank(record.course_id, checkpoints, record.course_time),
+            field_size: store.field(record.course_id, checkpoints).count() as u64,
+            tier: store.tier(record.course_id),
+        }).collect())
+    }
+
+    async fn recent_records(&self, checkpoints: bool, limit: u32) -> Result<Vec<RecentRecordRow>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        let mut records: Vec<&StoredRecord> = store.records.iter().filter(|record| record.checkpoints == checkpoints).collect();
+        records.sort_by(|a, b| b.date_set.cmp(&a.date_set).then(b.key.cmp(&a.key)));
+
+        Ok(records.into_iter().take(limit as usize).filter_map(|record|
+        {
+            let course = store.courses.iter().find(|course| course.course_id == record.course_id)?;
+            let beaten_before = store.history.iter().any(|history| history.course_id == record.course_id && history.checkpoints == checkpoints
+                && history.steamid != record.steamid && history.date_set <= record.date_set
+                && (history.course_time == record.course_time || beats(course.reverse != 0, history.course_time, record.course_time)));
+            Some(RecentRecordRow
+            {
+                map_name: course.mapfk.clone(),
+                course_id: course.course_id,
+                course_name: course.course_name.clone(),
+                player_name: store.player_name(record.steamid),
+                steamid: record.steamid,
+                course_time: record.course_time,
+                date: format_timestamp(record.date_set, "%Y-%m-%d"),
+                wr: !beaten_before as u64,
+            })
+        }).collect())
+    }
+
+    async fn course_history(&self, course_id: i32, checkpoints: bool) -> Result<Vec<HistoryRecord>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        let mut history: Vec<&StoredRecord> = store.history.iter().filter(|history| history.course_id == course_id && history.checkpoints == checkpoints).collect();
+        history.sort_by_key(|history| (history.date_set, history.key));
+
+        Ok(history.into_iter().map(|history| HistoryRecord
+        {
+            steamid: history.steamid,
+            player_name: store.player_name(history.steamid),
+            course_time: history.course_time,
+            date: format_timestamp(history.date_set, "%Y-%m-%d"),
+            timestamp: history.date_set,
+        }).collect())
+    }
+
+    async fn player_records(&self, steamid: SteamId, checkpoints: bool) -> Result<Vec<PlayerRecordRow>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        let mut records: Vec<&StoredRecord> = store.records.iter().filter(|record| record.steamid == steamid && record.checkpoints == checkpoints).collect();
+        records.sort_by(|a, b| b.date_set.cmp(&a.date_set).then(b.key.cmp(&a.key)));
+
+        Ok(records.into_iter().filter_map(|record|
+        {
+            let course = store.courses.iter().find(|course| course.course_id == record.course_id)?;
+            Some(PlayerRecordRow
+            {
+                course_id: course.course_id,
+                map_name: course.mapfk.clone(),
+                course_name: course.course_name.clone(),
+                course_time: record.course_time,
+                date: format_timestamp(record.date_set, "%Y-%m-%d"),
+                rank: store.rank(record.course_id, checkpoints, record.course_time),
+                field_size: store.field(record.course_id, checkpoints).count() as u64,
+                wr_time: store.wr_time(record.course_id, checkpoints).unwrap_or(record.course_time),
+                tier: store.tier(record.course_id),
+            })
+        }).collect())
+    }
+
+    async fn player_history(&self, steamid: SteamId, course_id: i32, checkpoints: bool) -> Result<Vec<HistoryRow>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        let mut history: Vec<&StoredRecord> = store.history.iter().filter(|history| history.steamid == steamid && history.course_id == course_id && history.checkpoints == checkpoints).collect();
+        history.sort_by_key(|history| (history.date_set, history.key));
+
+        Ok(history.into_iter().map(|history| HistoryRow
+        {
+            course_time: history.course_time,
+            date: format_timestamp(history.date_set, "%Y-%m-%d"),
+            timestamp: history.date_set,
+        }).collect())
+    }
+
+    async fn stale_players(&self, refreshed_before: i64, limit: u32) -> Result<Vec<SteamId>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        let mut stale: Vec<(Option<i64>, SteamId)> = store.players.iter()
+            .filter(|(_, player)| player.profile_updated_at.is_none_or(|updated_at| updated_at < refreshed_before))
+            .map(|(steamid, player)| (player.profile_updated_at, *steamid))
+            .collect();
+        stale.sort_by_key(|(updated_at, steamid)| (*updated_at, steamid.steamid64()));
+        Ok(stale.into_iter().take(limit as usize).map(|(_, steamid)| steamid).collect())
+    }
+
+    async fn update_player_profiles(&self, steamids: &[SteamId], profiles: &[PlayerProfile]) -> Result<(), sqlx::Error>
+    {
+        let mut store = self.store.lock().unwrap();
+        for profile in profiles
+        {
+            if let Some(player) = store.players.get_mut(&profile.steamid)
+            {
+                player.avatar = Some(profile.avatar.clone());
+                player.country = profile.loccountrycode.clone();
+            }
+        }
+        for steamid in steamids
+        {
+            if let Some(player) = store.players.get_mut(steamid)
+            {
+                player.profile_updated_at = Some(now());
+            }
+        }
+        Ok(())
+    }
+
+    async fn submit_record(&self, server_id: i32, course: &Course, steamid: SteamId, player_name: &str, course_time: CourseTime, checkpoints: bool) -> Result<SubmittedRecord, sqlx::Error>
+    {
+        let mut store = self.store.lock().unwrap();
+        store.players.entry(steamid).or_default().player_name = player_name.to_string();
+        if let Some(map) = store.maps.iter_mut().find(|map| map.map_name == course.mapfk)
+        {
+            map.times_played += 1;
+        }
+
+        let personal_best = store.field(course.course_id, checkpoints).find(|record| record.steamid == steamid).map(|record| record.course_time);
+        let improved = personal_best.is_none_or(|personal_best| beats(course.reverse != 0, course_time, personal_best));
+        if improved
+        {
+            store.set_record(steamid, course.course_id, checkpoints, course_time, now(), Some(server_id));
+        }
+
+        Ok(SubmittedRecord { improved, rank: store.rank(course.course_id, checkpoints, course_time) })
+    }
+
+    async fn server_key(&self, server_id: i32) -> Result<Option<ServerKey>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        Ok(store.servers.iter().find(|server| server.server_id == server_id).map(|server| ServerKey
+        {
+            server_id: server.server_id,
+            name: server.name.clone(),
+            key_hash: server.key_hash.clone(),
+        }))
+    }
+
+    async fn servers(&self) -> Result<Vec<ServerInfo>, sqlx::Error>
+    {
+        let store = self.store.lock().unwrap();
+        let format = "%Y-%m-%d %H:%M:%S";
+        Ok(store.servers.iter().map(|server| ServerInfo
+        {
+            server_id: server.server_id,
+            name: server.name.clone(),
+            created_at: format_timestamp(server.created_at, format),
+            key_rotated_at: server.key_rotated_at.and_then(|date| format_timestamp(date, format)),
+            revoked_at: server.revoked_at.and_then(|date| format_timestamp(date, format)),
+        }).collect())
+    }
+
+    async fn create_server(&self, name: &str, key_hash: &str) -> Result<i32, sqlx::Error>
+    {
+        Ok(self.add_server(name, key_hash))
+    }
+
+    async fn rotate_server_key(&self, server_id: i32, key_hash: &str) -> Result<Option<Server>, sqlx::Error>
+    {
+        let mut store = self.store.lock().unwrap();
+        Ok(store.servers.iter_mut().find(|server| server.server_id == server_id).map(|server|
+        {
+            server.key_hash = Some(key_hash.to_string());
+            server.key_rotated_at = Some(now());
+            server.revoked_at = None;
+            Server { server_id: server.server_id, name: server.name.clone() }
+        }))
+    }
+
+    async fn revoke_server(&self, server_id: i32) -> Result<bool, sqlx::Error>
+    {
+        let mut store = self.store.lock().unwrap();
+        Ok(store.servers.iter_mut().find(|server| server.server_id == server_id).map(|server|
+        {
+            server.key_hash = None;
+            server.revoked_at = server.revoked_at.or(Some(now()));
+        }).is_some())
+    }
+
+    async fn purge_server_records(&self, server_id: i32) -> Result<Option<PurgedRecords>, sqlx::Error>
+    {
+        let mut store = self.store.lock().unwrap();
+        if !store.servers.iter().any(|server| server.server_id == server_id)
+        {
+            return Ok(None);
+        }
+
+        let count = |records: &[StoredRecord], checkpoints: bool| records.iter().filter(|record| record.server_id == Some(server_id) && record.checkpoints == checkpoints).count() as u64;
+        let purged = PurgedRecords { records: count(&store.records, false), records_cp: count(&store.records, true) };
+        store.records.retain(|record| record.server_id != Some(server_id));
+        store.history.retain(|history| history.server_id != Some(server_id));
+
+        Ok(Some(purged))
+    }
+}
@@ -0,0 +1,288 @@
+use super::{record_table, PoolConfig, ToplistRepository};
+use crate::leaderboard::Placement;
+use crate::models::{HistoryRow, PlayerProfile, PlayerRecordRow, RecentRecordRow, RecordsSummary, SubmittedRecord};
+use crate::servers::{Server, ServerKey};
+use crate::wr_history::HistoryRecord;
+use axum::async_trait;
+use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
+use toplist_types::{Course, CourseName, CourseTime, Map, PurgedRecords, Record, RecordSort, ServerInfo, SteamId};
+
+#[derive(sqlx::FromRow)]
+struct PersonalBest
+{
+    course_time: CourseTime,
+}
+
+#[derive(sqlx::FromRow)]
+struct SubmissionRank
+{
+    rank: u64,
+}
+
+pub struct MySqlRepository
+{
+    pool: MySqlPool,
+}
+
+impl MySqlRepository
+{
+    pub fn new(pool: MySqlPool) -> Self
+    {
+        MySqlRepository { pool }
+    }
+
+    //NOTE: Migrations are off by default so the backend can run with a database user that can't alter the schema
+    pub async fn connect(database_url: &str, pool: &PoolConfig, run_migrations: bool) -> Result<Self, sqlx::Error>
+    {
+        let pool = MySqlPoolOptions::new()
+            .max_connections(pool.max_connections)
+            .min_connections(pool.min_connections)
+            .acquire_timeout(pool.acquire_timeout)
+            .connect(database_url).await?;
+        if run_migrations
+        {
+            sqlx::migrate!().run(&pool).await?;
+        }
+        Ok(MySqlRepository::new(pool))
+    }
+}
+
+//NOTE: Queries on the record tables are built at runtime so pro and checkpoints records share one query, the rest
+//are checked against the database at compile time. RANK is a reserved word since MySQL 8 and has to be quoted
+#[async_trait]
+impl ToplistRepository for MySqlRepository
+{
+    async fn maps(&self) -> Result<Vec<Map>, sqlx::Error>
+    {
+        sqlx::query_as!(Map, r#"SELECT map_name, times_played, DATE_FORMAT(map.time_added, '%Y-%m-%d') AS time_added FROM map"#).fetch_all(&self.pool).await
+    }
+
+    async fn course_names(&self, map_name: &str) -> Result<Vec<CourseName>, sqlx::Error>
+    {
+        //NOTE: Fetch courses in alphabetic order?
+        sqlx::query_as!(CourseName, r#"SELECT course_id, course_name FROM course WHERE mapfk=?"#, map_name).fetch_all(&self.pool).await
+    }
+
+    async fn course(&self, course_id: i32) -> Result<Option<Course>, sqlx::Error>
+    {
+        sqlx::query_as!(Course, r#"SELECT * FROM course WHERE course_id=?"#, course_id).fetch_optional(&self.pool).await
+    }
+
+    async fn course_by_name(&self, map_name: &str, course_name: &str) -> Result<Option<Course>, sqlx::Error>
+    {
+        sqlx::query_as!(Course, r#"SELECT * FROM course WHERE course_name=? AND mapfk=?"#, course_name, map_name).fetch_optional(&self.pool).await
+    }
+
+    async fn records_summary(&self, course: &Course, checkpoints: bool) -> Result<RecordsSummary, sqlx::Error>
+    {
+        let query = format!(r#"SELECT COUNT(*) AS records_count, IF(?, MAX(r.course_time), MIN(r.course_time)) AS wr_time
+                               FROM player p, {} r
+                               WHERE r.steamidfk = p.steamid AND r.courseidfk=?"#, record_table(checkpoints));
+        sqlx::query_as::<_, RecordsSummary>(&query).bind(course.reverse != 0).bind(course.course_id).fetch_one(&self.pool).await
+    }
+
+    //NOTE: Reverse courses are ranked by the longest time. Placeholders can't be used for the sort direction so the
+    //CASE expressions evaluate to NULL and fall through to the next key when a sort order doesn't apply
+    async fn records(&self, course: &Course, checkpoints: bool, sort: RecordSort, limit: u32, offset: u32) -> Result<Vec<Record>, sqlx::Error>
+    {
+        let reverse = course.reverse != 0;
+        let query = format!(r#"SELECT CAST(RANK() OVER (ORDER BY CASE WHEN ? THEN r.course_time END DESC, r.course_time ASC) AS UNSIGNED) AS `rank`,
+                               p.playername AS player_name, p.steamid, r.course_time, DATE_FORMAT(r.date_set, '%Y-%m-%d') AS date, r.course_time AS diff,
+                               p.avatar, p.loccountrycode AS country
+                               FROM player p, {} r
+                               WHERE r.steamidfk = p.steamid AND r.courseidfk=?
+                               ORDER BY CASE WHEN ?='date' THEN r.date_set END DESC, CASE WHEN ?='player' THEN p.playername END ASC,
+                                        CASE WHEN ? THEN r.course_time END DESC, r.course_time ASC, r.date_set ASC, r.record_key ASC
+                               LIMIT ? OFFSET ?"#, record_table(checkpoints));
+        sqlx::query_as::<_, Record>(&query)
+            .bind(reverse).bind(course.course_id).bind(sort.as_str()).bind(sort.as_str()).bind(reverse).bind(limit).bind(offset)
+            .fetch_all(&self.pool).await
+    }
+
+    //NOTE: Ranks every record on its course in one pass, the totals per player are added up in leaderboard::rank_players
+    async fn placements(&self, checkpoints: bool) -> Result<Vec<Placement>, sqlx::Error>
+    {
+        let query = format!(r#"SELECT p.steamid, p.playername AS player_name, p.avatar, p.loccountrycode AS country,
+                               CAST(RANK() OVER (PARTITION BY r.courseidfk ORDER BY CASE WHEN c.reverse <> 0 THEN r.course_time END DESC, r.course_time ASC) AS UNSIGNED) AS `rank`,
+                               CAST(COUNT(*) OVER (PARTITION BY r.courseidfk) AS UNSIGNED) AS field_size, t.tier
+                               FROM player p, {} r, course c LEFT JOIN map_tier t ON t.mapfk = c.mapfk
+                               WHERE r.steamidfk = p.steamid AND r.courseidfk = c.course_id"#, record_table(checkpoints));
+        sqlx::query_as::<_, Placement>(&query).fetch_all(&self.pool).await
+    }
+
+    //NOTE: A time was a new WR if nobody else had set an equal or better time on the course before it. Earlier personal bests
+    //of the same player are always slower so their own history is skipped
+    async fn recent_records(&self, checkpoints: bool, limit: u32) -> Result<Vec<RecentRecordRow>, sqlx::Error>
+    {
+        let query = format!(r#"SELECT m.map_name, c.course_id, c.course_name, p.playername AS player_name, p.steamid, r.course_time,
+                               DATE_FORMAT(r.date_set, '%Y-%m-%d') AS date,
+                               CAST(NOT EXISTS (SELECT 1 FROM record_history h
+                                                WHERE h.courseidfk = r.courseidfk AND h.checkpoints = ? AND h.steamidfk <> r.steamidfk AND h.date_set <= r.date_set
+                                                AND IF(c.reverse, h.course_time >= r.course_time, h.course_time <= r.course_time)) AS UNSIGNED) AS wr
+                               FROM {} r, course c, map m, player p
+                               WHERE r.courseidfk = c.course_id AND c.mapfk = m.map_name AND r.steamidfk = p.steamid
+                               ORDER BY r.date_set DESC, r.record_key DESC
+                               LIMIT ?"#, record_table(checkpoints));
+        sqlx::query_as::<_, RecentRecordRow>(&query).bind(checkpoints).bind(limit).fetch_all(&self.pool).await
+    }
+
+    async fn course_history(&self, course_id: i32, checkpoints: bool) -> Result<Vec<HistoryRecord>, sqlx::Error>
+    {
+        sqlx::query_as!(HistoryRecord, r#"SELECT p.steamid AS "steamid: SteamId", p.playername AS player_name, h.course_time AS "course_time: CourseTime",
+                                DATE_FORMAT(h.date_set, '%Y-%m-%d') AS date, CAST(UNIX_TIMESTAMP(h.date_set) AS SIGNED) AS "timestamp!: i64"
+                                FROM player p, record_history h
+                                WHERE h.steamidfk = p.steamid AND h.courseidfk=? AND h.checkpoints=?
+                                ORDER BY h.date_set ASC, h.history_key ASC"#, course_id, checkpoints).fetch_all(&self.pool).await
+    }
+
+    //NOTE: Records have to be ranked against everyone on the course before filtering down to the player, so only
+    //the courses the player has finished are ranked
+    async fn player_records(&self, steamid: SteamId, checkpoints: bool) -> Result<Vec<PlayerRecordRow>, sqlx::Error>
+    {
+        let steamid3 = steamid.steamid3();
+        let table = record_table(checkpoints);
+        let query = format!(r#"SELECT ranked.course_id, ranked.map_name, ranked.course_name, ranked.course_time, ranked.date,
+                               ranked.placement AS `rank`, ranked.field_size, ranked.wr_time, ranked.tier
+                               FROM (SELECT r.steamidfk, r.date_set, r.record_key, c.course_id, m.map_name, c.course_name, r.course_time, DATE_FORMAT(r.date_set, '%Y-%m-%d') AS date, t.tier,
+                                     CAST(RANK() OVER course_window AS UNSIGNED) AS placement,
+                                     CAST(COUNT(*) OVER (PARTITION BY r.courseidfk) AS UNSIGNED) AS field_size,
+                                     FIRST_VALUE(r.course_time) OVER course_window AS wr_time
+                                     FROM player p, map m, {table} r, course c LEFT JOIN map_tier t ON t.mapfk = c.mapfk
+                                     WHERE r.steamidfk = p.steamid AND c.mapfk=m.map_name AND r.courseidfk=c.course_id
+                                     AND r.courseidfk IN (SELECT courseidfk FROM {table} WHERE steamidfk=?)
+                                     WINDOW course_window AS (PARTITION BY r.courseidfk ORDER BY CASE WHEN c.reverse <> 0 THEN r.course_time END DESC, r.course_time ASC)) ranked
+                               WHERE ranked.steamidfk=?
+                               ORDER BY ranked.date_set DESC, ranked.record_key DESC"#);
+        sqlx::query_as::<_, PlayerRecordRow>(&query).bind(&steamid3).bind(&steamid3).fetch_all(&self.pool).await
+    }
+
+    async fn player_history(&self, steamid: SteamId, course_id: i32, checkpoints: bool) -> Result<Vec<HistoryRow>, sqlx::Error>
+    {
+        sqlx::query_as!(HistoryRow, r#"SELECT h.course_time AS "course_time: CourseTime", DATE_FORMAT(h.date_set, '%Y-%m-%d') AS date, CAST(UNIX_TIMESTAMP(h.date_set) AS SIGNED) AS "timestamp!: i64"
+                                FROM record_history h
+                                WHERE h.steamidfk=? AND h.courseidfk=? AND h.checkpoints=?
+                                ORDER BY h.date_set ASC, h.history_key ASC"#, steamid.steamid3(), course_id, checkpoints).fetch_all(&self.pool).await
+    }
+
+    async fn stale_players(&self, refreshed_before: i64, limit: u32) -> Result<Vec<SteamId>, sqlx::Error>
+    {
+        sqlx::query_scalar!(r#"SELECT steamid AS "steamid: SteamId" FROM player
+                               WHERE profile_updated_at IS NULL OR profile_updated_at < FROM_UNIXTIME(?)
+                               ORDER BY profile_updated_at ASC, steamid ASC
+                               LIMIT ?"#, refreshed_before, limit).fetch_all(&self.pool).await
+    }
+
+    async fn update_player_profiles(&self, steamids: &[SteamId], profiles: &[PlayerProfile]) -> Result<(), sqlx::Error>
+    {
+        let mut tx = self.pool.begin().await?;
+        for profile in profiles
+        {
+            sqlx::query!(r#"UPDATE player SET personaname=?, avatar=?, avatarmedium=?, avatarfull=?, loccountrycode=? WHERE steamid=?"#,
+                         profile.personaname, profile.avatar, profile.avatarmedium, profile.avatarfull, profile.loccountrycode, profile.steamid.steamid3()).execute(&mut tx).await?;
+        }
+        for steamid in steamids
+        {
+            sqlx::query!(r#"UPDATE player SET profile_updated_at=NOW() WHERE steamid=?"#, steamid.steamid3()).execute(&mut tx).await?;
+        }
+        tx.commit().await
+    }
+
+    async fn submit_record(&self, server_id: i32, course: &Course, steamid: SteamId, player_name: &str, course_time: CourseTime, checkpoints: bool) -> Result<SubmittedRecord, sqlx::Error>
+    {
+        let reverse = course.reverse != 0;
+        let steamid3 = steamid.steamid3();
+        let table = record_table(checkpoints);
+
+        //NOTE: Reverse courses are won by the longest time
+        let beats = |time: CourseTime, other: CourseTime| if reverse { time > other } else { time < other };
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(r#"INSERT INTO player (steamid, playername) VALUES (?, ?) ON DUPLICATE KEY UPDATE playername = VALUES(playername)"#, steamid3, player_name).execute(&mut tx).await?;
+
+        sqlx::query!(r#"UPDATE map SET times_played = COALESCE(times_played, 0) + 1 WHERE map_name=?"#, course.mapfk).execute(&mut tx).await?;
+
+        let personal_best = sqlx::query_as::<_, PersonalBest>(&format!(r#"SELECT course_time FROM {} WHERE steamidfk=? AND courseidfk=? FOR UPDATE"#, table))
+            .bind(&steamid3).bind(course.course_id).fetch_optional(&mut tx).await?;
+        let improved = match personal_best
+        {
+            None =>
+            {
+                sqlx::query(&format!(r#"INSERT INTO {} (steamidfk, courseidfk, course_time, date_set, serverfk) VALUES (?, ?, ?, NOW(), ?)"#, table))
+                    .bind(&steamid3).bind(course.course_id).bind(course_time).bind(server_id).execute(&mut tx).await?;
+                true
+            }
+            Some(personal_best) if beats(course_time, personal_best.course_time) =>
+            {
+                sqlx::query(&format!(r#"UPDATE {} SET course_time=?, date_set=NOW(), serverfk=? WHERE steamidfk=? AND courseidfk=?"#, table))
+                    .bind(course_time).bind(server_id).bind(&steamid3).bind(course.course_id).execute(&mut tx).await?;
+                true
+            }
+            Some(_) => false,
+        };
+
+        //NOTE: Times that beat the submitted one, which includes the player's own personal best when it wasn't improved
+        let rank = sqlx::query_as::<_, SubmissionRank>(&format!(r#"SELECT CAST(COUNT(*) + 1 AS UNSIGNED) AS `rank` FROM {}
+                                                                   WHERE courseidfk=? AND IF(?, course_time > ?, course_time < ?)"#, table))
+            .bind(course.course_id).bind(reverse).bind(course_time).bind(course_time).fetch_one(&mut tx).await?.rank;
+
+        tx.commit().await?;
+
+        Ok(SubmittedRecord { improved, rank })
+    }
+
+    async fn server_key(&self, server_id: i32) -> Result<Option<ServerKey>, sqlx::Error>
+    {
+        sqlx::query_as!(ServerKey, r#"SELECT server_id, name, key_hash FROM servers WHERE server_id=?"#, server_id).fetch_optional(&self.pool).await
+    }
+
+    async fn servers(&self) -> Result<Vec<ServerInfo>, sqlx::Error>
+    {
+        sqlx::query_as!(ServerInfo, r#"SELECT server_id, name, DATE_FORMAT(created_at, '%Y-%m-%d %H:%i:%s') AS created_at,
+                                    DATE_FORMAT(key_rotated_at, '%Y-%m-%d %H:%i:%s') AS key_rotated_at, DATE_FORMAT(revoked_at, '%Y-%m-%d %H:%i:%s') AS revoked_at
+                                    FROM servers ORDER BY server_id"#).fetch_all(&self.pool).await
+    }
+
+    async fn create_server(&self, name: &str, key_hash: &str) -> Result<i32, sqlx::Error>
+    {
+        Ok(sqlx::query!(r#"INSERT INTO servers (name, key_hash) VALUES (?, ?)"#, name, key_hash).execute(&self.pool).await?.last_insert_id() as i32)
+    }
+
+    //NOTE: Rotating a revoked key reinstates the server
+    async fn rotate_server_key(&self, server_id: i32, key_hash: &str) -> Result<Option<Server>, sqlx::Error>
+    {
+        let server = sqlx::query_as!(Server, r#"SELECT server_id, name FROM servers WHERE server_id=?"#, server_id).fetch_optional(&self.pool).await?;
+        if server.is_some()
+        {
+            sqlx::query!(r#"UPDATE servers SET key_hash=?, key_rotated_at=NOW(), revoked_at=NULL WHERE server_id=?"#, key_hash, server_id).execute(&self.pool).await?;
+        }
+        Ok(server)
+    }
+
+    //NOTE: MySQL only counts the rows an UPDATE changed, so an already revoked server is looked up first instead
+    async fn revoke_server(&self, server_id: i32) -> Result<bool, sqlx::Error>
+    {
+        if sqlx::query_as!(Server, r#"SELECT server_id, name FROM servers WHERE server_id=?"#, server_id).fetch_optional(&self.pool).await?.is_none()
+        {
+            return Ok(false);
+        }
+        sqlx::query!(r#"UPDATE servers SET key_hash=NULL, revoked_at=COALESCE(revoked_at, NOW()) WHERE server_id=?"#, server_id).execute(&self.pool).await?;
+        Ok(true)
+    }
+
+    async fn purge_server_records(&self, server_id: i32) -> Result<Option<PurgedRecords>, sqlx::Error>
+    {
+        if sqlx::query_as!(Server, r#"SELECT server_id, name FROM servers WHERE server_id=?"#, server_id).fetch_optional(&self.pool).await?.is_none()
+        {
+            return Ok(None);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let records = sqlx::query!(r#"DELETE FROM record WHERE serverfk=?"#, server_id).execute(&mut tx).await?.rows_affected();
+        let records_cp = sqlx::query!(r#"DELETE FROM record_cp WHERE serverfk=?"#, server_id).execute(&mut tx).await?.rows_affected();
+        sqlx::query!(r#"DELETE FROM record_history WHERE serverfk=?"#, server_id).execute(&mut tx).await?;
+        tx.commit().await?;
+
+        Ok(Some(PurgedRecords { records, records_cp }))
+    }
+}
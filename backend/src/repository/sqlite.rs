@@ -0,0 +1,515 @@
+use super::{record_table, PoolConfig, ToplistRepository};
+use crate::leaderboard::Placement;
+use crate::models::{HistoryRow, PlayerProfile, PlayerRecordRow, RecentRecordRow, RecordsSummary, SubmittedRecord};
+use crate::servers::{Server, ServerKey};
+use crate::wr_history::HistoryRecord;
+use axum::async_trait;
+use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use toplist_types::{Course, CourseName, CourseTime, Map, PurgedRecords, Record, RecordSort, ServerInfo, SteamId};
+
+//NOTE: The query macros can only be checked against one database per build, so SQLite goes through the runtime
+//query functions. SQLite has no unsigned 64 bit integers, ranks come back as i64 and are converted to the shared rows
+#[derive(sqlx::FromRow)]
+struct SqliteRecord
+{
+    rank: i64,
+    player_name: String,
+    steamid: SteamId,
+    course_time: CourseTime,
+    date: Option<String>,
+    avatar: Option<String>,
+    country: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SqlitePlacement
+{
+    steamid: SteamId,
+    player_name: String,
+    avatar: Option<String>,
+    country: Option<String>,
+    rank: i64,
+    field_size: i64,
+    tier: Option<u8>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SqlitePlayerRecord
+{
+    course_id: i32,
+    map_name: String,
+    course_name: String,
+    course_time: CourseTime,
+    date: Option<String>,
+    rank: i64,
+    field_size: i64,
+    wr_time: CourseTime,
+    tier: Option<u8>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SqliteRecentRecord
+{
+    map_name: String,
+    course_id: i32,
+    course_name: String,
+    player_name: String,
+    steamid: SteamId,
+    course_time: CourseTime,
+    date: Option<String>,
+    wr: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct PersonalBest
+{
+    course_time: CourseTime,
+}
+
+#[derive(sqlx::FromRow)]
+struct SubmissionRank
+{
+    rank: i64,
+}
+
+pub struct SqliteRepository
+{
+    pool: SqlitePool,
+}
+
+impl SqliteRepository
+{
+    pub fn new(pool: SqlitePool) -> Self
+    {
+        SqliteRepository { pool }
+    }
+
+    //NOTE: The database file is created if it doesn't exist yet, which only makes sense with the migrations run
+    pub async fn connect(database_url: &str, pool: &PoolConfig, run_migrations: bool) -> Result<Self, sqlx::Error>
+    {
+        let options = database_url.parse::<sqlx::sqlite::SqliteConnectOptions>()?.create_if_missing(run_migrations);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(pool.max_connections)
+            .min_connections(pool.min_connections)
+            .acquire_timeout(pool.acquire_timeout)
+            .connect_with(options).await?;
+        if run_migrations
+        {
+            sqlx::migrate!("./migrations/sqlite").run(&pool).await?;
+        }
+        Ok(SqliteRepository::new(pool))
+    }
+}
+
+#[async_trait]
+impl ToplistRepository for SqliteRepository
+{
+    async fn maps(&self) -> Result<Vec<Map>, sqlx::Error>
+    {
+        sqlx::query_as::<_, Map>(r#"SELECT map_name, times_played, strftime('%Y-%m-%d', time_added) AS time_added FROM map"#).fetch_all(&self.pool).await
+    }
+
+    async fn course_names(&self, map_name: &str) -> Result<Vec<CourseName>, sqlx::Error>
+    {
+        sqlx::query_as::<_, CourseName>(r#"SELECT course_id, course_name FROM course WHERE mapfk=?"#).bind(map_name).fetch_all(&self.pool).await
+    }
+
+    async fn course(&self, course_id: i32) -> Result<Option<Course>, sqlx::Error>
+    {
+        sqlx::query_as::<_, Course>(r#"SELECT * FROM course WHERE course_id=?"#).bind(course_id).fetch_optional(&self.pool).await
+    }
+
+    async fn course_by_name(&self, map_name: &str, course_name: &str) -> Result<Option<Course>, sqlx::Error>
+    {
+        sqlx::query_as::<_, Course>(r#"SELECT * FROM course WHERE course_name=? AND mapfk=?"#).bind(course_name).bind(map_name).fetch_optional(&self.pool).await
+    }
+
+    async fn records_summary(&self, course: &Course, checkpoints: bool) -> Result<RecordsSummary, sqlx::Error>
+    {
+        let query = format!(r#"SELECT COUNT(*) AS records_count, CASE WHEN ? THEN MAX(r.course_time) ELSE MIN(r.course_time) END AS wr_time
+                               FROM player p, {} r
+                               WHERE r.steamidfk = p.steamid AND r.courseidfk=?"#, record_table(checkpoints));
+        sqlx::query_as::<_, RecordsSummary>(&query).bind(course.reverse != 0).bind(course.course_id).fetch_one(&self.pool).await
+    }
+
+    async fn records(&self, course: &Course, checkpoints: bool, sort: RecordSort, limit: u32, offset: u32) -> Result<Vec<Record>, sqlx::Error>
+    {
+        let reverse = course.reverse != 0;
+        let query = format!(r#"SELECT RANK() OVER (ORDER BY CASE WHEN ? THEN r.course_time END DESC, r.course_time ASC) AS rank,
+                               p.playername AS player_name, p.steamid, r.course_time, strftime('%Y-%m-%d', r.date_set) AS date,
+                               p.avatar, p.loccountrycode AS country
+                               FROM player p, {} r
+                               WHERE r.steamidfk = p.steamid AND r.courseidfk=?
+                               ORDER BY CASE WHEN ?='date' THEN r.date_set END DESC, CASE WHEN ?='player' THEN p.playername END ASC,
+                                        CASE WHEN ? THEN r.course_time END DESC, r.course_time ASC, r.date_set ASC, r.record_key ASC
+                               LIMIT ? OFFSET ?"#, record_table(checkpoints));
+        let rows = sqlx::query_as::<_, SqliteRecord>(&query)
+            .bind(reverse).bind(course.course_id).bind(sort.as_str()).bind(sort.as_str()).bind(reverse).bind(limit).bind(offset)
+            .fetch_all(&self.pool).await?;
+
+        Ok(rows.into_iter().map(|row| Record
+        {
+            rank: row.rank as u64,
+            player_name: row.player_name,
+            steamid: row.steamid,
+            course_time: row.course_time,
+            date: row.date,
+            diff: String::new(),
+            avatar: row.avatar,
+            country: row.country,
+        }).collect())
+    }
+
+    async fn placements(&self, checkpoints: bool) -> Result<Vec<Placement>, sqlx::Error>
+    {
+        let query = format!(r#"SELECT p.steamid, p.playername AS player_name, p.avatar, p.loccountrycode AS country,
+                               RANK() OVER (PARTITION BY r.courseidfk ORDER BY CASE WHEN c.reverse <> 0 THEN r.course_time END DESC, r.course_time ASC) AS rank,
+                               COUNT(*) OVER (PARTITION BY r.courseidfk) AS field_size, t.tier
+                               FROM player p, {} r, course c LEFT JOIN map_tier t ON t.mapfk = c.mapfk
+                               WHERE r.steamidfk = p.steamid AND r.courseidfk = c.course_id"#, record_table(checkpoints));
+        let rows = sqlx::query_as::<_, SqlitePlacement>(&query).fetch_all(&self.pool).await?;
+
+        Ok(rows.into_iter().map(|row| Placement
+        {
+            steamid: row.steamid,
+            player_name: row.player_name,
+            avatar: row.avatar,
+            country: row.country,
+            rank: row.rank as u64,
+            field_size: row.field_size as u64,
+            tier: row.tier,
+        }).collect())
+    }
+
+    async fn recent_records(&self, checkpoints: bool, limit: u32) -> Result<Vec<RecentRecordRow>, sqlx::Error>
+    {
+        let query = format!(r#"SELECT m.map_name, c.course_id, c.course_name, p.playername AS player_name, p.steamid, r.course_time,
+                               strftime('%Y-%m-%d', r.date_set) AS date,
+                               NOT EXISTS (SELECT 1 FROM record_history h
+                                           WHERE h.courseidfk = r.courseidfk AND h.checkpoints = ? AND h.steamidfk <> r.steamidfk AND h.date_set <= r.date_set
+                                           AND CASE WHEN c.reverse <> 0 THEN h.course_time >= r.course_time ELSE h.course_time <= r.course_time END) AS wr
+                               FROM {} r, course c, map m, player p
+                               WHERE r.courseidfk = c.course_id AND c.mapfk = m.map_name AND r.steamidfk = p.steamid
+                               ORDER BY r.date_set DESC, r.record_key DESC
+                               LIMIT ?"#, record_table(checkpoints));
+        let rows = sqlx::query_as::<_, SqliteRecentRecord>(&query).bind(checkpoints).bind(limit).fetch_all(&self.pool).await?;
+
+        Ok(rows.into_iter().map(|row| RecentRecordRow
+        {
+            map_name: row.map_name,
+            course_id: row.course_id,
+            course_name: row.course_name,
+            player_name: row.player_name,
+            steamid: row.steamid,
+            course_time: row.course_time,
+            date: row.date,
+            wr: row.wr as u64,
+        }).collect())
+    }
+
+    async fn course_history(&self, course_id: i32, checkpoints: bool) -> Result<Vec<HistoryRecord>, sqlx::Error>
+    {
+        sqlx::query_as::<_, HistoryRecord>(r#"SELECT p.steamid, p.playername AS player_name, h.course_time,
+                                              strftime('%Y-%m-%d', h.date_set) AS date, CAST(strftime('%s', h.date_set) AS INTEGER) AS timestamp
+                                              FROM player p, record_history h
+                                              WHERE h.steamidfk = p.steamid AND h.courseidfk=? AND h.checkpoints=?
+                                              ORDER BY h.date_set ASC, h.history_key ASC"#).bind(course_id).bind(checkpoints).fetch_all(&self.pool).await
+    }
+
+    async fn player_records(&self, steamid: SteamId, checkpoints: bool) -> Result<Vec<PlayerRecordRow>, sqlx::Error>
+    {
+        let steamid3 = steamid.steamid3();
+        let table = record_table(checkpoints);
+        let query = format!(r#"SELECT ranked.course_id, ranked.map_name, ranked.course_name, ranked.course_time, ranked.date,
+                               ranked.placement AS rank, ranked.field_size, ranked.wr_time, ranked.tier
+                               FROM (SELECT r.steamidfk, r.date_set, r.record_key, c.course_id, m.map_name, c.course_name, r.course_time, strftime('%Y-%m-%d', r.date_set) AS date, t.tier,
+                                     RANK() OVER course_window AS placement,
+                                     COUNT(*) OVER (PARTITION BY r.courseidfk) AS field_size,
+                                     FIRST_VALUE(r.course_time) OVER course_window AS wr_time
+                                     FROM player p, map m, {table} r, course c LEFT JOIN map_tier t ON t.mapfk = c.mapfk
+                                     WHERE r.steamidfk = p.steamid AND c.mapfk=m.map_name AND r.courseidfk=c.course_id
+                                     AND r.courseidfk IN (SELECT courseidfk FROM {table} WHERE steamidfk=?)
+                                     WINDOW course_window AS (PARTITION BY r.courseidfk ORDER BY CASE WHEN c.reverse <> 0 THEN r.course_time END DESC, r.course_time ASC)) ranked
+                               WHERE ranked.steamidfk=?
+                               ORDER BY ranked.date_set DESC, ranked.record_key DESC"#);
+        let rows = sqlx::query_as::<_, SqlitePlayerRecord>(&query).bind(&steamid3).bind(&steamid3).fetch_all(&self.pool).await?;
+
+        Ok(rows.into_iter().map(|row| PlayerRecordRow
+        {
+            course_id: row.course_id,
+            map_name: row.map_name,
+            course_name: row.course_name,
+            course_time: row.course_time,
+            date: row.date,
+            rank: row.rank as u64,
+            field_size: row.field_size as u64,
+            wr_time: row.wr_time,
+            tier: row.tier,
+        }).collect())
+    }
+
+    async fn player_history(&self, steamid: SteamId, course_id: i32, checkpoints: bool) -> Result<Vec<HistoryRow>, sqlx::Error>
+    {
+        sqlx::query_as::<_, HistoryRow>(r#"SELECT h.course_time, strftime('%Y-%m-%d', h.date_set) AS date, CAST(strftime('%s', h.date_set) AS INTEGER) AS timestamp
+                                           FROM record_history h
+                                           WHERE h.steamidfk=? AND h.courseidfk=? AND h.checkpoints=?
+                                           ORDER BY h.date_set ASC, h.history_key ASC"#).bind(steamid.steamid3()).bind(course_id).bind(checkpoints).fetch_all(&self.pool).await
+    }
+
+    async fn stale_players(&self, refreshed_before: i64, limit: u32) -> Result<Vec<SteamId>, sqlx::Error>
+    {
+        sqlx::query_scalar::<_, SteamId>(r#"SELECT steamid FROM player
+                                            WHERE profile_updated_at IS NULL OR profile_updated_at < datetime(?, 'unixepoch')
+                                            ORDER BY profile_updated_at ASC, steamid ASC
+                                            LIMIT ?"#).bind(refreshed_before).bind(limit).fetch_all(&self.pool).await
+    }
+
+    async fn update_player_profiles(&self, steamids: &[SteamId], profiles: &[PlayerProfile]) -> Result<(), sqlx::Error>
+    {
+        let mut tx = self.pool.begin().await?;
+        for profile in profiles
+        {
+            sqlx::query(r#"UPDATE player SET personaname=?, avatar=?, avatarmedium=?, avatarfull=?, loccountrycode=? WHERE steamid=?"#)
+                .bind(&profile.personaname).bind(&profile.avatar).bind(&profile.avatarmedium).bind(&profile.avatarfull).bind(&profile.loccountrycode).bind(profile.steamid.steamid3())
+                .execute(&mut tx).await?;
+        }
+        for steamid in steamids
+        {
+            sqlx::query(r#"UPDATE player SET profile_updated_at=datetime('now') WHERE steamid=?"#).bind(steamid.steamid3()).execute(&mut tx).await?;
+        }
+        tx.commit().await
+    }
+
+    //NOTE: SQLite has no SELECT ... FOR UPDATE. The player upsert is the first statement so the transaction holds the
+    //database's write lock before the personal best is read
+    async fn submit_record(&self, server_id: i32, course: &Course, steamid: SteamId, player_name: &str, course_time: CourseTime, checkpoints: bool) -> Result<SubmittedRecord, sqlx::Error>
+    {
+        let reverse = course.reverse != 0;
+        let steamid3 = steamid.steamid3();
+        let table = record_table(checkpoints);
+
+        //NOTE: Reverse courses are won by the longest time
+        let beats = |time: CourseTime, other: CourseTime| if reverse { time > other } else { time < other };
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(r#"INSERT INTO player (steamid, playername) VALUES (?, ?) ON CONFLICT (steamid) DO UPDATE SET playername = excluded.playername"#)
+            .bind(&steamid3).bind(player_name).execute(&mut tx).await?;
+
+        sqlx::query(r#"UPDATE map SET times_played = COALESCE(times_played, 0) + 1 WHERE map_name=?"#).bind(&course.mapfk).execute(&mut tx).await?;
+
+        let personal_best = sqlx::query_as::<_, PersonalBest>(&format!(r#"SELECT course_time FROM {} WHERE steamidfk=? AND courseidfk=?"#, table))
+            .bind(&steamid3).bind(course.course_id).fetch_optional(&mut tx).await?;
+        let improved = match personal_best
+        {
+            None =>
+            {
+                sqlx::query(&format!(r#"INSERT INTO {} (steamidfk, courseidfk, course_time, date_set, serverfk) VALUES (?, ?, ?, datetime('now'), ?)"#, table))
+                    .bind(&steamid3).bind(course.course_id).bind(course_time).bind(server_id).execute(&mut tx).await?;
+                true
+            }
+            Some(personal_best) if beats(course_time, personal_best.course_time) =>
+            {
+                sqlx::query(&format!(r#"UPDATE {} SET course_time=?, date_set=datetime('now'), serverfk=? WHERE steamidfk=? AND courseidfk=?"#, table))
+                    .bind(course_time).bind(server_id).bind(&steamid3).bind(course.course_id).execute(&mut tx).await?;
+                true
+            }
+            Some(_) => false,
+        };
+
+        //NOTE: Times that beat the submitted one, which includes the player's own personal best when it wasn't improved
+        let rank = sqlx::query_as::<_, SubmissionRank>(&format!(r#"SELECT COUNT(*) + 1 AS rank FROM {}
+                                                                   WHERE courseidfk=? AND CASE WHEN ? THEN course_time > ? ELSE course_time < ? END"#, table))
+            .bind(course.course_id).bind(reverse).bind(course_time).bind(course_time).fetch_one(&mut tx).await?.rank as u64;
+
+        tx.commit().await?;
+
+        Ok(SubmittedRecord { improved, rank })
+    }
+
+    async fn server_key(&self, server_id: i32) -> Result<Option<ServerKey>, sqlx::Error>
+    {
+        sqlx::query_as::<_, ServerKey>(r#"SELECT server_id, name, key_hash FROM servers WHERE server_id=?"#).bind(server_id).fetch_optional(&self.pool).await
+    }
+
+    async fn servers(&self) -> Result<Vec<ServerInfo>, sqlx::Error>
+    {
+        sqlx::query_as::<_, ServerInfo>(r#"SELECT server_id, name, strftime('%Y-%m-%d %H:%M:%S', created_at) AS created_at,
+                                           strftime('%Y-%m-%d %H:%M:%S', key_rotated_at) AS key_rotated_at, strftime('%Y-%m-%d %H:%M:%S', revoked_at) AS revoked_at
+                                           FROM servers ORDER BY server_id"#).fetch_all(&self.pool).await
+    }
+
+    async fn create_server(&self, name: &str, key_hash: &str) -> Result<i32, sqlx::Error>
+    {
+        Ok(sqlx::query(r#"INSERT INTO servers (name, key_hash) VALUES (?, ?)"#).bind(name).bind(key_hash).execute(&self.pool).await?.last_insert_rowid() as i32)
+    }
+
+    //NOTE: Rotating a revoked key reinstates the server
+    async fn rotate_server_key(&self, server_id: i32, key_hash: &str) -> Result<Option<Server>, sqlx::Error>
+    {
+        let server = sqlx::query_as::<_, Server>(r#"SELECT server_id, name FROM servers WHERE server_id=?"#).bind(server_id).fetch_optional(&self.pool).await?;
+        if server.is_some()
+        {
+            sqlx::query(r#"UPDATE servers SET key_hash=?, key_rotated_at=datetime('now'), revoked_at=NULL WHERE server_id=?"#).bind(key_hash).bind(server_id).execute(&self.pool).await?;
+        }
+        Ok(server)
+    }
+
+    async fn revoke_server(&self, server_id: i32) -> Result<bool, sqlx::Error>
+    {
+        let revoked = sqlx::query(r#"UPDATE servers SET key_hash=NULL, revoked_at=COALESCE(revoked_at, datetime('now')) WHERE server_id=?"#).bind(server_id).execute(&self.pool).await?.rows_affected();
+        Ok(revoked > 0)
+    }
+
+    async fn purge_server_records(&self, server_id: i32) -> Result<Option<PurgedRecords>, sqlx::Error>
+    {
+        if sqlx::query_as::<_, Server>(r#"SELECT server_id, name FROM servers WHERE server_id=?"#).bind(server_id).fetch_optional(&self.pool).await?.is_none()
+        {
+            return Ok(None);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let records = sqlx::query(r#"DELETE FROM record WHERE serverfk=?"#).bind(server_id).execute(&mut tx).await?.rows_affected();
+        let records_cp = sqlx::query(r#"DELETE FROM record_cp WHERE serverfk=?"#).bind(server_id).execute(&mut tx).await?.rows_affected();
+        sqlx::query(r#"DELETE FROM record_history WHERE serverfk=?"#).bind(server_id).execute(&mut tx).await?;
+        tx.commit().await?;
+
+        Ok(Some(PurgedRecords { records, records_cp }))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::servers::ApiKey;
+
+    //NOTE: Every connection to :memory: opens its own database, so the pool is kept to a single connection that never expires
+    async fn repository() -> SqliteRepository
+    {
+        let pool = SqlitePoolOptions::new().max_connections(1).idle_timeout(None).max_lifetime(None).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations/sqlite").run(&pool).await.unwrap();
+        sqlx::query(r#"INSERT INTO map (map_name) VALUES ('kz_test'), ('kz_reverse')"#).execute(&pool).await.unwrap();
+        sqlx::query(r#"INSERT INTO course (course_id, course_name, mapfk, reverse) VALUES (1, 'main', 'kz_test', 0), (2, 'main', 'kz_reverse', 1)"#).execute(&pool).await.unwrap();
+        sqlx::query(r#"INSERT INTO servers (server_id, name) VALUES (1, 'test server')"#).execute(&pool).await.unwrap();
+        SqliteRepository::new(pool)
+    }
+
+    fn player(account_id: u32) -> SteamId
+    {
+        SteamId::from_account_id(1, account_id).unwrap()
+    }
+
+    async fn submit(repository: &SqliteRepository, course_id: i32, account_id: u32, time: &str) -> SubmittedRecord
+    {
+        let course = repository.course(course_id).await.unwrap().unwrap();
+        repository.submit_record(1, &course, player(account_id), &format!("player {}", account_id), time.parse().unwrap(), false).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn keeps_personal_bests_and_their_history()
+    {
+        let repository = repository().await;
+
+        let first = submit(&repository, 1, 1, "00:01:30.000").await;
+        let slower = submit(&repository, 1, 1, "00:01:40.000").await;
+        let faster = submit(&repository, 1, 1, "00:01:20.000").await;
+        let second = submit(&repository, 1, 2, "00:01:25.000").await;
+
+        assert!(first.improved && faster.improved && second.improved);
+        assert!(!slower.improved);
+        assert_eq!([first.rank, slower.rank, faster.rank, second.rank], [1, 2, 1, 2]);
+
+        let history = repository.player_history(player(1), 1, false).await.unwrap();
+        assert_eq!(history.iter().map(|row| row.course_time.to_string()).collect::<Vec<String>>(), ["01:30.00", "01:20.00"]);
+        assert!(repository.player_history(player(1), 1, true).await.unwrap().is_empty());
+
+        let maps = repository.maps().await.unwrap();
+        assert_eq!(maps.iter().find(|map| map.map_name == "kz_test").unwrap().times_played, Some(4));
+
+        let recent = repository.recent_records(false, 10).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent.iter().map(|row| row.wr).sum::<u64>(), 1);
+    }
+
+    #[tokio::test]
+    async fn ranks_records_on_reverse_courses()
+    {
+        let repository = repository().await;
+        submit(&repository, 2, 1, "00:05:00.000").await;
+        submit(&repository, 2, 2, "00:06:00.000").await;
+        submit(&repository, 2, 3, "00:04:00.000").await;
+
+        let course = repository.course(2).await.unwrap().unwrap();
+        let summary = repository.records_summary(&course, false).await.unwrap();
+        let records = repository.records(&course, false, RecordSort::Time, 2, 0).await.unwrap();
+
+        assert_eq!(summary.records_count, 3);
+        assert_eq!(summary.wr_time.unwrap().to_string(), "06:00.00");
+        assert_eq!(records.iter().map(|record| (record.rank, record.player_name.as_str())).collect::<Vec<(u64, &str)>>(), [(1, "player 2"), (2, "player 1")]);
+
+        let by_player = repository.records(&course, false, RecordSort::Player, 10, 1).await.unwrap();
+        assert_eq!(by_player.iter().map(|record| record.rank).collect::<Vec<u64>>(), [1, 3]);
+
+        let placements = repository.placements(false).await.unwrap();
+        assert!(placements.iter().all(|placement| placement.field_size == 3 && placement.tier.is_none()));
+
+        let player_records = repository.player_records(player(3), false).await.unwrap();
+        assert_eq!(player_records.len(), 1);
+        assert_eq!((player_records[0].rank, player_records[0].wr_time.to_string()), (3, "06:00.00".to_string()));
+        assert_eq!(repository.course_history(2, false).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn manages_servers()
+    {
+        let repository = repository().await;
+        submit(&repository, 1, 1, "00:01:30.000").await;
+
+        let key = ApiKey::generate(0);
+        let server_id = repository.create_server("new server", &key.hash().unwrap()).await.unwrap();
+        let server_key = repository.server_key(server_id).await.unwrap().unwrap();
+        assert!(key.verify(&server_key.key_hash.unwrap()));
+
+        assert!(repository.revoke_server(server_id).await.unwrap());
+        assert!(repository.server_key(server_id).await.unwrap().unwrap().key_hash.is_none());
+        assert!(repository.servers().await.unwrap().iter().any(|server| server.server_id == server_id && server.revoked_at.is_some()));
+        assert!(repository.rotate_server_key(server_id, "hash").await.unwrap().is_some());
+        assert!(repository.rotate_server_key(99, "hash").await.unwrap().is_none());
+
+        let purged = repository.purge_server_records(1).await.unwrap().unwrap();
+        assert_eq!((purged.records, purged.records_cp), (1, 0));
+        assert!(repository.course_history(1, false).await.unwrap().is_empty());
+        assert!(repository.purge_server_records(99).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn refreshes_player_profiles()
+    {
+        let repository = repository().await;
+        submit(&repository, 1, 1, "00:01:30.000").await;
+        submit(&repository, 1, 2, "00:01:40.000").await;
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64;
+
+        assert_eq!(repository.stale_players(now, 10).await.unwrap(), [player(1), player(2)]);
+
+        let profile = PlayerProfile
+        {
+            steamid: player(2),
+            personaname: "steam 2".to_string(),
+            avatar: "avatar.jpg".to_string(),
+            avatarmedium: "avatar_medium.jpg".to_string(),
+            avatarfull: "avatar_full.jpg".to_string(),
+            loccountrycode: Some("FI".to_string()),
+        };
+        repository.update_player_profiles(&[player(2)], &[profile]).await.unwrap();
+        assert_eq!(repository.stale_players(now - 60, 10).await.unwrap(), [player(1)]);
+        assert_eq!(repository.stale_players(now + 60, 1).await.unwrap(), [player(1)]);
+
+        let course = repository.course(1).await.unwrap().unwrap();
+        let records = repository.records(&course, false, RecordSort::Time, 10, 0).await.unwrap();
+        assert_eq!(records.iter().map(|record| (record.avatar.as_deref(), record.country.as_deref())).collect::<Vec<(Option<&str>, Option<&str>)>>(), [(None, None), (Some("avatar.jpg"), Some("FI"))]);
+        let placements = repository.placements(false).await.unwrap();
+        assert_eq!(placements.iter().find(|placement| placement.steamid == player(2)).unwrap().country.as_deref(), Some("FI"));
+    }
+}
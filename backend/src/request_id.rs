@@ -0,0 +1,70 @@
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use rand_core::{OsRng, RngCore};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+tokio::task_local!
+{
+    static REQUEST_ID: String;
+}
+
+//NOTE: ID of the request being handled, None outside of the middleware
+pub fn current() -> Option<String>
+{
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+//NOTE: Keeps the ID a proxy in front of the backend has set so the logs of both can be matched, otherwise makes a new one.
+//Everything logged while handling the request is in a span with the ID, and the response returns it in x-request-id
+pub async fn middleware<B>(request: Request<B>, next: Next<B>) -> Response
+{
+    let id = match request.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok())
+    {
+        Some(id) if is_valid(id) => id.to_string(),
+        _ => generate(),
+    };
+    let span = tracing::info_span!("request", id = %id, method = %request.method(), uri = %request.uri());
+
+    let mut response = REQUEST_ID.scope(id.clone(), next.run(request)).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&id)
+    {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+fn generate() -> String
+{
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn is_valid(id: &str) -> bool
+{
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn request_ids()
+    {
+        let id = generate();
+        assert_eq!(id.len(), 32);
+        assert!(is_valid(&id));
+        assert_ne!(id, generate());
+
+        assert!(is_valid("5f0c2a9e-1b7d-4c3e-9a8f-2d6b1e0c7a4f"));
+        assert!(!is_valid(""));
+        assert!(!is_valid("id with spaces"));
+        assert!(!is_valid(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+}
@@ -0,0 +1,190 @@
+use serde::Deserialize;
+use std::fmt;
+
+//NOTE: Points for a record are a share of the points its map tier is worth:
+//tier_points * (completion_share + (1 - completion_share) * percentile + rank_bonus[rank - 1])
+//where percentile is how much of the field the record beats, 1.0 for the WR and 0.0 for the slowest time
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringConfig
+{
+    pub tier_points: Vec<f64>,      //NOTE: Points a course on a tier 1, 2, ... map is worth
+    pub default_tier: u8,           //NOTE: Tier of maps without a row in map_tier
+    pub completion_share: f64,      //NOTE: Share of the tier points given just for finishing the course
+    pub rank_bonus: Vec<f64>,       //NOTE: Extra share of the tier points for placing 1st, 2nd, ...
+}
+
+impl Default for ScoringConfig
+{
+    fn default() -> Self
+    {
+        ScoringConfig
+        {
+            tier_points: vec![100.0, 200.0, 400.0, 600.0, 800.0, 1000.0, 1200.0],
+            default_tier: 1,
+            completion_share: 0.5,
+            rank_bonus: vec![0.5, 0.3, 0.2, 0.1, 0.1, 0.05, 0.05, 0.05, 0.05, 0.05],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ScoringConfigError
+{
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(&'static str),
+}
+
+impl fmt::Display for ScoringConfigError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            ScoringConfigError::Io(e) => write!(f, "unable to read scoring config: {}", e),
+            ScoringConfigError::Parse(e) => write!(f, "unable to parse scoring config: {}", e),
+            ScoringConfigError::Invalid(reason) => write!(f, "invalid scoring config: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ScoringConfigError {}
+
+impl ScoringConfig
+{
+    //NOTE: A missing file falls back to the default formula
+    pub fn load(path: &str) -> Result<Self, ScoringConfigError>
+    {
+        match std::fs::read_to_string(path)
+        {
+            Ok(contents) => ScoringConfig::parse(&contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ScoringConfig::default()),
+            Err(e) => Err(ScoringConfigError::Io(e)),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, ScoringConfigError>
+    {
+        let config: ScoringConfig = toml::from_str(contents).map_err(ScoringConfigError::Parse)?;
+
+        if config.tier_points.is_empty()
+        {
+            return Err(ScoringConfigError::Invalid("tier_points can't be empty"));
+        }
+        if config.tier_points.iter().chain(config.rank_bonus.iter()).any(|points| !points.is_finite() || *points < 0.0)
+        {
+            return Err(ScoringConfigError::Invalid("tier_points and rank_bonus must be positive numbers"));
+        }
+        if !(0.0..=1.0).contains(&config.completion_share)
+        {
+            return Err(ScoringConfigError::Invalid("completion_share must be between 0 and 1"));
+        }
+        if config.default_tier == 0 || config.default_tier as usize > config.tier_points.len()
+        {
+            return Err(ScoringConfigError::Invalid("default_tier must be one of the tiers in tier_points"));
+        }
+
+        Ok(config)
+    }
+
+    //NOTE: Tiers above the highest configured tier are worth as much as the highest tier
+    pub fn points(&self, rank: u64, field_size: u64, tier: Option<u8>) -> u64
+    {
+        let tier = tier.filter(|tier| *tier > 0).unwrap_or(self.default_tier) as usize;
+        let tier_points = self.tier_points[tier.min(self.tier_points.len()) - 1];
+
+        let rank = rank.max(1);
+        let field_size = field_size.max(rank);
+        let percentile = if field_size <= 1 { 1.0 } else { (field_size - rank) as f64 / (field_size - 1) as f64 };
+        let rank_bonus = self.rank_bonus.get(rank as usize - 1).copied().unwrap_or(0.0);
+
+        (tier_points * (self.completion_share + (1.0 - self.completion_share) * percentile + rank_bonus)).round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //NOTE: (rank, field size, tier, points with the shipped scoring.toml)
+    const FIXTURE: [(u64, u64, Option<u8>, u64); 12] = [
+        (1, 1, Some(1), 150),
+        (1, 10, Some(3), 600),
+        (2, 10, Some(3), 498),
+        (3, 10, Some(3), 436),
+        (10, 10, Some(3), 220),
+        (11, 40, Some(3), 349),
+        (40, 40, Some(3), 200),
+        (1, 25, None, 150),
+        (5, 25, Some(0), 102),
+        (1, 3, Some(7), 1800),
+        (2, 3, Some(9), 1260),
+        (3, 3, Some(7), 840),
+    ];
+
+    fn shipped_config() -> ScoringConfig
+    {
+        ScoringConfig::parse(include_str!("../scoring.toml")).unwrap()
+    }
+
+    #[test]
+    fn shipped_config_matches_default()
+    {
+        assert_eq!(shipped_config(), ScoringConfig::default());
+    }
+
+    #[test]
+    fn pins_formula_against_fixture()
+    {
+        let config = shipped_config();
+
+        for (rank, field_size, tier, points) in FIXTURE
+        {
+            assert_eq!(config.points(rank, field_size, tier), points, "rank {} of {} on tier {:?}", rank, field_size, tier);
+        }
+    }
+
+    #[test]
+    fn pins_fixture_totals()
+    {
+        let config = shipped_config();
+        let total: u64 = FIXTURE.iter().map(|(rank, field_size, tier, _)| config.points(*rank, *field_size, *tier)).sum();
+
+        assert_eq!(total, 6605);
+    }
+
+    #[test]
+    fn better_placements_never_score_less()
+    {
+        let config = shipped_config();
+
+        for field_size in 1..60
+        {
+            for rank in 2..=field_size
+            {
+                assert!(config.points(rank - 1, field_size, Some(2)) >= config.points(rank, field_size, Some(2)));
+            }
+        }
+    }
+
+    #[test]
+    fn partial_config_uses_defaults()
+    {
+        let config = ScoringConfig::parse("tier_points = [10, 20]\ncompletion_share = 1.0\nrank_bonus = []").unwrap();
+
+        assert_eq!(config.points(1, 10, Some(2)), 20);
+        assert_eq!(config.points(10, 10, Some(1)), 10);
+        assert_eq!(config.default_tier, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_config()
+    {
+        for contents in ["tier_points = []", "completion_share = 1.5", "default_tier = 8", "rank_bonus = [-1.0]", "tiers = [1]", "tier_points = \"a lot\""]
+        {
+            assert!(ScoringConfig::parse(contents).is_err(), "{}", contents);
+        }
+    }
+}
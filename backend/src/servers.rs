@@ -0,0 +1,199 @@
+use crate::error::ApiError;
+use crate::repository::ToplistRepository;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequestParts, TypedHeader};
+use axum::headers::{authorization::Bearer, Authorization};
+use axum::http::request::Parts;
+use rand_core::{OsRng, RngCore};
+use serde::Serialize;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+
+const SECRET_BYTES: usize = 32;
+
+//NOTE: A game server allowed to submit records, resolved from the API key it sent
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Server
+{
+    pub server_id: i32,
+    pub name: String,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct ServerKey
+{
+    pub server_id: i32,
+    pub name: String,
+    pub key_hash: Option<String>,   //NOTE: NULL once the key is revoked
+}
+
+//NOTE: Keys look like <server_id>.<secret> so the argon2 hash to check against can be looked up by id
+pub struct ApiKey
+{
+    pub server_id: i32,
+    pub secret: String,
+}
+
+impl ApiKey
+{
+    pub fn generate(server_id: i32) -> Self
+    {
+        let mut bytes = [0u8; SECRET_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        ApiKey { server_id, secret: bytes.iter().map(|b| format!("{:02x}", b)).collect() }
+    }
+
+    pub fn parse(key: &str) -> Option<Self>
+    {
+        let (server_id, secret) = key.split_once('.')?;
+        if secret.is_empty()
+        {
+            return None;
+        }
+        Some(ApiKey { server_id: server_id.parse().ok()?, secret: secret.to_string() })
+    }
+
+    pub fn hash(&self) -> Result<String, argon2::password_hash::Error>
+    {
+        let salt = SaltString::generate(&mut OsRng);
+        Ok(Argon2::default().hash_password(self.secret.as_bytes(), &salt)?.to_string())
+    }
+
+    pub fn verify(&self, key_hash: &str) -> bool
+    {
+        PasswordHash::new(key_hash).is_ok_and(|hash| Argon2::default().verify_password(self.secret.as_bytes(), &hash).is_ok())
+    }
+}
+
+impl std::fmt::Display for ApiKey
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "{}.{}", self.server_id, self.secret)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Server
+where
+    Arc<dyn ToplistRepository>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection>
+    {
+        let TypedHeader(Authorization(bearer)) = TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await.map_err(|_| ApiError::Unauthorized)?;
+        let key = ApiKey::parse(bearer.token()).ok_or(ApiError::Unauthorized)?;
+        let repository = Arc::<dyn ToplistRepository>::from_ref(state);
+
+        let server = repository.server_key(key.server_id).await?.ok_or(ApiError::Unauthorized)?;
+        let key_hash = server.key_hash.ok_or(ApiError::Unauthorized)?;
+
+        //NOTE: argon2 is deliberately slow so it's kept off the async workers
+        let valid = tokio::task::spawn_blocking(move || key.verify(&key_hash)).await.unwrap_or(false);
+        if !valid
+        {
+            return Err(ApiError::Unauthorized);
+        }
+
+        Ok(Server { server_id: server.server_id, name: server.name })
+    }
+}
+
+//NOTE: Key of the admin endpoints from the config, they are disabled when there's none
+#[derive(Clone, Default)]
+pub struct AdminKey(Option<Arc<str>>);
+
+impl AdminKey
+{
+    pub fn new(key: Option<String>) -> Self
+    {
+        AdminKey(key.filter(|key| !key.is_empty()).map(Arc::from))
+    }
+
+    //NOTE: Constant time so the response time doesn't give away how much of the key a guess got right
+    fn matches(&self, token: &str) -> bool
+    {
+        match &self.0
+        {
+            Some(key) => key.as_bytes().ct_eq(token.as_bytes()).into(),
+            None => false,
+        }
+    }
+}
+
+//NOTE: Guards the admin endpoints with the admin_api_key setting
+pub struct Admin;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Admin
+where
+    AdminKey: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection>
+    {
+        let TypedHeader(Authorization(bearer)) = TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await.map_err(|_| ApiError::Unauthorized)?;
+
+        if !AdminKey::from_ref(state).matches(bearer.token())
+        {
+            return Err(ApiError::Unauthorized);
+        }
+        Ok(Admin)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn checks_the_admin_key()
+    {
+        let admin_key = AdminKey::new(Some("admin key".to_string()));
+
+        assert!(admin_key.matches("admin key"));
+        assert!(!admin_key.matches("admin"));
+        assert!(!admin_key.matches("admin kez"));
+        assert!(!AdminKey::new(Some(String::new())).matches(""));
+        assert!(!AdminKey::default().matches("admin key"));
+    }
+
+    #[test]
+    fn generated_keys_round_trip()
+    {
+        let key = ApiKey::generate(42);
+        let parsed = ApiKey::parse(&key.to_string()).unwrap();
+
+        assert_eq!(key.secret.len(), 2 * SECRET_BYTES);
+        assert_eq!(parsed.server_id, 42);
+        assert_eq!(parsed.secret, key.secret);
+        assert_ne!(ApiKey::generate(42).secret, key.secret);
+    }
+
+    #[test]
+    fn rejects_malformed_keys()
+    {
+        for key in ["", "42", "42.", ".secret", "server.secret"]
+        {
+            assert!(ApiKey::parse(key).is_none(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn verifies_against_hash()
+    {
+        let key = ApiKey::generate(7);
+        let key_hash = key.hash().unwrap();
+
+        assert!(key.verify(&key_hash));
+        assert!(!ApiKey::generate(7).verify(&key_hash));
+        assert!(!key.verify("not a hash"));
+    }
+}
@@ -0,0 +1,174 @@
+use super::{Player, SteamClient};
+use axum::async_trait;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use toplist_types::SteamId;
+
+//NOTE: Entries are dropped once they are older than the TTL, expired entries are swept out whenever new ones are added
+struct TtlMap<K, V>
+{
+    ttl: Duration,
+    entries: HashMap<K, (Instant, V)>,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlMap<K, V>
+{
+    fn new(ttl: Duration) -> Self
+    {
+        TtlMap { ttl, entries: HashMap::new() }
+    }
+
+    fn get(&self, key: &K) -> Option<V>
+    {
+        self.entries.get(key).filter(|(cached_at, _)| cached_at.elapsed() < self.ttl).map(|(_, value)| value.clone())
+    }
+
+    fn insert(&mut self, key: K, value: V)
+    {
+        let ttl = self.ttl;
+        self.entries.retain(|_, (cached_at, _)| cached_at.elapsed() < ttl);
+        self.entries.insert(key, (Instant::now(), value));
+    }
+}
+
+//NOTE: Keeps player summaries and resolved vanity URLs around for the TTL so profile views don't each call Steam. Only
+//the SteamIDs missing from the cache are passed on, in a single call. Vanity URLs that don't resolve are cached as well
+pub struct CachedSteamClient<C>
+{
+    client: C,
+    players: Mutex<TtlMap<SteamId, Player>>,
+    vanities: Mutex<TtlMap<String, Option<SteamId>>>,
+}
+
+impl<C: SteamClient> CachedSteamClient<C>
+{
+    pub fn new(client: C, ttl: Duration) -> Self
+    {
+        CachedSteamClient
+        {
+            client,
+            players: Mutex::new(TtlMap::new(ttl)),
+            vanities: Mutex::new(TtlMap::new(ttl)),
+        }
+    }
+}
+
+#[async_trait]
+impl<C: SteamClient> SteamClient for CachedSteamClient<C>
+{
+    async fn resolve_vanity(&self, name: &str) -> Result<Option<SteamId>, reqwest::Error>
+    {
+        //NOTE: Vanity URLs are case insensitive
+        let name = name.to_lowercase();
+        if let Some(steamid) = self.vanities.lock().unwrap().get(&name)
+        {
+            return Ok(steamid);
+        }
+
+        let steamid = self.client.resolve_vanity(&name).await?;
+        self.vanities.lock().unwrap().insert(name, steamid);
+        Ok(steamid)
+    }
+
+    async fn player_summaries(&self, steamids: &[SteamId]) -> Result<Vec<Player>, reqwest::Error>
+    {
+        let mut missing: Vec<SteamId> = Vec::new();
+        {
+            let players = self.players.lock().unwrap();
+            for steamid in steamids
+            {
+                if players.get(steamid).is_none() && !missing.contains(steamid)
+                {
+                    missing.push(*steamid);
+                }
+            }
+        }
+
+        if !missing.is_empty()
+        {
+            let fetched = self.client.player_summaries(&missing).await?;
+            let mut players = self.players.lock().unwrap();
+            for player in fetched
+            {
+                if let Ok(steamid) = player.steamid.parse::<SteamId>()
+                {
+                    players.insert(steamid, player);
+                }
+            }
+        }
+
+        let players = self.players.lock().unwrap();
+        let mut found: Vec<SteamId> = Vec::new();
+        Ok(steamids.iter().filter_map(|steamid|
+        {
+            if found.contains(steamid)
+            {
+                return None;
+            }
+            found.push(*steamid);
+            players.get(steamid)
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::steam::FakeSteamClient;
+
+    fn player(account_id: u32) -> SteamId
+    {
+        SteamId::from_account_id(1, account_id).unwrap()
+    }
+
+    fn fake() -> FakeSteamClient
+    {
+        let fake = FakeSteamClient::new();
+        fake.add_player(player(1), "player 1", Some("FI"));
+        fake.add_player(player(2), "player 2", None);
+        fake.add_vanity("player1", player(1));
+        fake
+    }
+
+    #[tokio::test]
+    async fn only_fetches_missing_players()
+    {
+        let steam = CachedSteamClient::new(fake(), Duration::from_secs(60));
+
+        let players = steam.player_summaries(&[player(1)]).await.unwrap();
+        assert_eq!(players.iter().map(|player| player.personaname.as_str()).collect::<Vec<&str>>(), ["player 1"]);
+
+        let players = steam.player_summaries(&[player(2), player(1), player(2), player(3)]).await.unwrap();
+        assert_eq!(players.iter().map(|player| player.personaname.as_str()).collect::<Vec<&str>>(), ["player 2", "player 1"]);
+        assert_eq!(steam.client.requests(), 2);
+
+        steam.player_summaries(&[player(1), player(2)]).await.unwrap();
+        assert_eq!(steam.client.requests(), 2);
+    }
+
+    #[tokio::test]
+    async fn caches_vanity_urls()
+    {
+        let steam = CachedSteamClient::new(fake(), Duration::from_secs(60));
+
+        assert_eq!(steam.resolve_vanity("player1").await.unwrap(), Some(player(1)));
+        assert_eq!(steam.resolve_vanity("Player1").await.unwrap(), Some(player(1)));
+        assert_eq!(steam.resolve_vanity("nobody").await.unwrap(), None);
+        assert_eq!(steam.resolve_vanity("nobody").await.unwrap(), None);
+        assert_eq!(steam.client.requests(), 2);
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_fetched_again()
+    {
+        let steam = CachedSteamClient::new(fake(), Duration::ZERO);
+
+        steam.player_summaries(&[player(1)]).await.unwrap();
+        steam.player_summaries(&[player(1)]).await.unwrap();
+
+        assert_eq!(steam.client.requests(), 2);
+    }
+}
@@ -0,0 +1,71 @@
+use super::{Player, SteamClient};
+use axum::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use toplist_types::SteamId;
+
+#[derive(Default)]
+struct Profiles
+{
+    players: HashMap<SteamId, Player>,
+    vanities: HashMap<String, SteamId>,
+    requests: usize,
+}
+
+//NOTE: Answers from profiles added with the add_ functions instead of calling Steam, for tests and for running the
+//backend without an API key, where main uses it with no profiles. Every call counts as one request
+#[derive(Default)]
+pub struct FakeSteamClient
+{
+    profiles: Mutex<Profiles>,
+}
+
+impl FakeSteamClient
+{
+    pub fn new() -> Self
+    {
+        FakeSteamClient::default()
+    }
+
+    //NOTE: Fills in the SteamID and profile URL, the rest of the profile is left empty
+    pub fn add_player(&self, steamid: SteamId, personaname: &str, loccountrycode: Option<&str>)
+    {
+        let player = Player
+        {
+            steamid: steamid.to_string(),
+            personaname: personaname.to_string(),
+            profileurl: steamid.profile_url(),
+            loccountrycode: loccountrycode.map(str::to_string),
+            ..Player::default()
+        };
+        self.profiles.lock().unwrap().players.insert(steamid, player);
+    }
+
+    pub fn add_vanity(&self, name: &str, steamid: SteamId)
+    {
+        self.profiles.lock().unwrap().vanities.insert(name.to_string(), steamid);
+    }
+
+    pub fn requests(&self) -> usize
+    {
+        self.profiles.lock().unwrap().requests
+    }
+}
+
+#[async_trait]
+impl SteamClient for FakeSteamClient
+{
+    async fn resolve_vanity(&self, name: &str) -> Result<Option<SteamId>, reqwest::Error>
+    {
+        let mut profiles = self.profiles.lock().unwrap();
+        profiles.requests += 1;
+        Ok(profiles.vanities.get(name).copied())
+    }
+
+    async fn player_summaries(&self, steamids: &[SteamId]) -> Result<Vec<Player>, reqwest::Error>
+    {
+        let mut profiles = self.profiles.lock().unwrap();
+        profiles.requests += 1;
+        Ok(steamids.iter().filter_map(|steamid| profiles.players.get(steamid).cloned()).collect())
+    }
+}
@@ -0,0 +1,133 @@
+use super::{Player, SteamClient, SteamResponse};
+use axum::async_trait;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::time::Duration;
+use toplist_types::SteamId;
+
+const API_URL: &str = "https://api.steampowered.com/ISteamUser";
+const MAX_STEAMIDS_PER_REQUEST: usize = 100;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_RETRIES: u32 = 3;
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct VanityResponse
+{
+    response: VanityResult,
+}
+
+#[derive(Deserialize)]
+struct VanityResult
+{
+    success: u32,                   //NOTE: 1 - resolved, 42 - no match
+    steamid: Option<String>,
+}
+
+//NOTE: Talks to the Steam Web API directly, wrap it in a CachedSteamClient to avoid calling Steam on every page view
+pub struct HttpSteamClient
+{
+    client: reqwest::Client,
+    api_key: String,
+}
+
+impl HttpSteamClient
+{
+    pub fn new(api_key: String) -> Self
+    {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Unable to create HTTP client.");
+        HttpSteamClient { client, api_key }
+    }
+
+    //NOTE: Retries when Steam is rate limiting us (429) or having trouble (5xx), waiting as long as Retry-After asks or
+    //backing off exponentially. Timeouts are retried the same way
+    async fn get<T: DeserializeOwned>(&self, method: &str, query: &[(&str, &str)]) -> Result<T, reqwest::Error>
+    {
+        let url = format!("{}/{}", API_URL, method);
+        let mut attempt = 0;
+        loop
+        {
+            let response = self.client.get(&url).query(&[("key", self.api_key.as_str())]).query(query).send().await;
+            let retry = match &response
+            {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS || response.status().is_server_error() => Some(retry_after(response)),
+                Err(e) if e.is_timeout() => Some(None),
+                _ => None,
+            };
+
+            match retry
+            {
+                Some(retry_after) if attempt < MAX_RETRIES =>
+                {
+                    let delay = retry_delay(attempt, retry_after);
+                    tracing::warn!("Steam API {} failed ({}), retrying in {:?}", method, response.map(|response| response.status().to_string()).unwrap_or_else(|e| e.to_string()), delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => return response?.error_for_status()?.json::<T>().await,
+            }
+        }
+    }
+}
+
+fn retry_after(response: &Response) -> Option<Duration>
+{
+    response.headers().get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+fn retry_delay(attempt: u32, retry_after: Option<Duration>) -> Duration
+{
+    retry_after.unwrap_or_else(|| BASE_RETRY_DELAY * 2u32.pow(attempt)).min(MAX_RETRY_DELAY)
+}
+
+#[async_trait]
+impl SteamClient for HttpSteamClient
+{
+    async fn resolve_vanity(&self, name: &str) -> Result<Option<SteamId>, reqwest::Error>
+    {
+        let resolved = self.get::<VanityResponse>("ResolveVanityURL/v0001/", &[("vanityurl", name)]).await?.response;
+        Ok(resolved.steamid.filter(|_| resolved.success == 1).and_then(|steamid| steamid.parse().ok()))
+    }
+
+    async fn player_summaries(&self, steamids: &[SteamId]) -> Result<Vec<Player>, reqwest::Error>
+    {
+        let mut players = Vec::with_capacity(steamids.len());
+        for batch in steamids.chunks(MAX_STEAMIDS_PER_REQUEST)
+        {
+            let batch = batch.iter().map(|steamid| steamid.to_string()).collect::<Vec<String>>().join(",");
+            players.extend(self.get::<SteamResponse>("GetPlayerSummaries/v0002/", &[("steamids", &batch)]).await?.response.players);
+        }
+        Ok(players)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially()
+    {
+        let delays: Vec<Duration> = (0..MAX_RETRIES).map(|attempt| retry_delay(attempt, None)).collect();
+
+        assert_eq!(delays, [Duration::from_millis(500), Duration::from_secs(1), Duration::from_secs(2)]);
+        assert_eq!(retry_delay(10, None), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn honours_retry_after()
+    {
+        assert_eq!(retry_delay(0, Some(Duration::from_secs(5))), Duration::from_secs(5));
+        assert_eq!(retry_delay(0, Some(Duration::from_secs(3600))), MAX_RETRY_DELAY);
+    }
+}
@@ -0,0 +1,22 @@
+pub mod cache;
+pub mod fake;
+pub mod http;
+
+pub use cache::CachedSteamClient;
+pub use fake::FakeSteamClient;
+pub use http::HttpSteamClient;
+pub use toplist_types::{Player, Players, SteamResponse};
+
+use axum::async_trait;
+use toplist_types::SteamId;
+
+//NOTE: Calls to the Steam Web API go through this so they can be cached and faked in tests. One client is shared
+//through the app state
+#[async_trait]
+pub trait SteamClient: Send + Sync
+{
+    //NOTE: None when no profile uses the vanity URL
+    async fn resolve_vanity(&self, name: &str) -> Result<Option<SteamId>, reqwest::Error>;
+    //NOTE: Any number of SteamIDs, profiles that don't exist are left out of the result
+    async fn player_summaries(&self, steamids: &[SteamId]) -> Result<Vec<Player>, reqwest::Error>;
+}
@@ -0,0 +1,113 @@
+use toplist_types::{CourseTime, SteamId, WrEntry};
+
+//NOTE: A personal best from record_history, the rows of a course are expected in the order they were set
+#[derive(sqlx::FromRow)]
+pub struct HistoryRecord
+{
+    pub steamid: SteamId,
+    pub player_name: String,
+    pub course_time: CourseTime,
+    pub date: Option<String>,
+    pub timestamp: i64,             //NOTE: Unix timestamp of date_set
+}
+
+//NOTE: Walks the history in order and keeps every time that beat the WR standing at that point. Reverse courses are won
+//by the longest time. A time equal to the WR doesn't take it over. WRs are returned newest first
+pub fn world_records(history: Vec<HistoryRecord>, reverse: bool, now: i64) -> Vec<WrEntry>
+{
+    let mut wrs: Vec<(WrEntry, i64)> = Vec::new();
+
+    for record in history
+    {
+        let previous = wrs.last().map(|(wr, _)| wr.course_time);
+        let beats_wr = match previous
+        {
+            Some(wr) if reverse => record.course_time > wr,
+            Some(wr) => record.course_time < wr,
+            None => true,
+        };
+        if !beats_wr
+        {
+            continue;
+        }
+
+        if let Some((wr, set_at)) = wrs.last_mut()
+        {
+            wr.stood_seconds = (record.timestamp - *set_at).max(0);
+        }
+        wrs.push((WrEntry
+        {
+            steamid: record.steamid,
+            player_name: record.player_name,
+            course_time: record.course_time,
+            date: record.date,
+            stood_seconds: (now - record.timestamp).max(0),
+            diff: previous.map(|wr| record.course_time - wr),
+        }, record.timestamp));
+    }
+
+    wrs.into_iter().rev().map(|(wr, _)| wr).collect()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn record(account_id: u32, time: &str, day: i64) -> HistoryRecord
+    {
+        HistoryRecord
+        {
+            steamid: SteamId::from_account_id(1, account_id).unwrap(),
+            player_name: format!("player {}", account_id),
+            course_time: time.parse().unwrap(),
+            date: None,
+            timestamp: day * DAY,
+        }
+    }
+
+    #[test]
+    fn keeps_only_times_that_beat_the_wr()
+    {
+        let history = vec![
+            record(1, "00:01:30.00", 0),
+            record(2, "00:01:35.00", 1),
+            record(2, "00:01:20.00", 3),
+            record(1, "00:01:20.00", 4),
+            record(3, "00:01:10.50", 10),
+        ];
+
+        let wrs = world_records(history, false, 15 * DAY);
+
+        assert_eq!(wrs.len(), 3);
+        assert_eq!(wrs.iter().map(|wr| wr.course_time.to_string()).collect::<Vec<String>>(), ["01:10.50", "01:20.00", "01:30.00"]);
+        assert_eq!(wrs.iter().map(|wr| wr.player_name.as_str()).collect::<Vec<&str>>(), ["player 3", "player 2", "player 1"]);
+        assert_eq!(wrs.iter().map(|wr| wr.stood_seconds / DAY).collect::<Vec<i64>>(), [5, 7, 3]);
+        assert_eq!(wrs.iter().map(|wr| wr.diff.map(|diff| diff.to_string())).collect::<Vec<Option<String>>>(), [Some("-00:09.50".to_string()), Some("-00:10.00".to_string()), None]);
+    }
+
+    #[test]
+    fn reverse_courses_keep_the_longest_time()
+    {
+        let history = vec![
+            record(1, "00:05:00.00", 0),
+            record(2, "00:04:00.00", 1),
+            record(2, "00:06:00.00", 2),
+        ];
+
+        let wrs = world_records(history, true, 2 * DAY);
+
+        assert_eq!(wrs.len(), 2);
+        assert_eq!(wrs[0].course_time.to_string(), "06:00.00");
+        assert_eq!(wrs[0].diff.unwrap().to_string(), "+01:00.00");
+        assert_eq!(wrs[0].stood_seconds, 0);
+    }
+
+    #[test]
+    fn empty_history_has_no_wrs()
+    {
+        assert!(world_records(Vec::new(), false, 0).is_empty());
+    }
+}
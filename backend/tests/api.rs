@@ -0,0 +1,456 @@
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use backend::api::{self, AppState};
+use backend::repository::memory::InMemoryRepository;
+use backend::scoring::ScoringConfig;
+use backend::servers::{AdminKey, ApiKey};
+use backend::steam::FakeSteamClient;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use toplist_types::{ErrorCode, ErrorResponse, SteamId};
+use tower::ServiceExt;
+
+const DAY: i64 = 24 * 60 * 60;
+const ADMIN_KEY: &str = "admin key";
+
+fn player(account_id: u32) -> SteamId
+{
+    SteamId::from_account_id(1, account_id).unwrap()
+}
+
+struct TestApp
+{
+    router: Router,
+    server_key: ApiKey,
+    main_course: i32,
+    reverse_course: i32,
+}
+
+//NOTE: Two maps, kz_test with a regular and a bonus course and kz_reverse with a reverse course. Player 1 from Finland
+//holds the WR on kz_test main, player 2 from Sweden on kz_reverse
+fn app() -> TestApp
+{
+    let repository = InMemoryRepository::new();
+    repository.add_map("kz_test", Some(3));
+    repository.add_map("kz_reverse", None);
+    let main_course = repository.add_course("kz_test", "main", false);
+    repository.add_course("kz_test", "bonus", false);
+    let reverse_course = repository.add_course("kz_reverse", "main", true);
+
+    repository.add_record(main_course, player(1), "player 1", "00:01:40.000".parse().unwrap(), false, DAY);
+    repository.add_record(main_course, player(1), "player 1", "00:01:30.000".parse().unwrap(), false, 3 * DAY);
+    repository.add_record(main_course, player(2), "player 2", "00:01:35.500".parse().unwrap(), false, 2 * DAY);
+    repository.add_record(main_course, player(2), "player 2", "00:02:10.000".parse().unwrap(), true, 2 * DAY);
+    repository.add_record(reverse_course, player(1), "player 1", "00:05:00.000".parse().unwrap(), false, 4 * DAY);
+    repository.add_record(reverse_course, player(2), "player 2", "00:06:00.000".parse().unwrap(), false, 5 * DAY);
+    repository.add_profile(player(1), "avatar1.jpg", Some("FI"));
+    repository.add_profile(player(2), "avatar2.jpg", Some("SE"));
+
+    let steam = FakeSteamClient::new();
+    steam.add_player(player(1), "steam 1", Some("FI"));
+    steam.add_player(player(2), "steam 2", None);
+    steam.add_vanity("player1", player(1));
+
+    let mut server_key = ApiKey::generate(0);
+    server_key.server_id = repository.add_server("test server", &server_key.hash().unwrap());
+
+    let state = AppState
+    {
+        repository: Arc::new(repository),
+        steam: Arc::new(steam),
+        scoring: Arc::new(ScoringConfig::default()),
+        admin_key: AdminKey::new(Some(ADMIN_KEY.to_string())),
+    };
+
+    TestApp { router: api::router(state), server_key, main_course, reverse_course }
+}
+
+impl TestApp
+{
+    async fn request(&self, method: Method, uri: &str, bearer: Option<&str>, body: Option<Value>) -> (StatusCode, Value)
+    {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(bearer) = bearer
+        {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", bearer));
+        }
+        let body = match body
+        {
+            Some(body) =>
+            {
+                request = request.header(header::CONTENT_TYPE, "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+
+        let response = self.router.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    async fn get(&self, uri: &str) -> (StatusCode, Value)
+    {
+        self.request(Method::GET, uri, None, None).await
+    }
+}
+
+//NOTE: Rejections of the extractors have to come back in the same error body as the handlers' errors
+fn assert_bad_request(status: StatusCode, body: Value)
+{
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body: ErrorResponse = serde_json::from_value(body).unwrap();
+    assert_eq!(body.error.code, ErrorCode::BadRequest);
+    assert!(!body.error.message.is_empty());
+    assert!(body.error.request_id.is_some());
+}
+
+fn strings(values: &Value, field: &str) -> Vec<String>
+{
+    values.as_array().unwrap().iter().map(|value| value[field].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn maps()
+{
+    let app = app();
+
+    let (status, body) = app.get("/api/maps").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(strings(&body["maps"], "map_name"), ["kz_test", "kz_reverse"]);
+    assert_eq!(body["maps"][0]["times_played"], 0);
+    assert!(body["maps"][0]["time_added"].is_string());
+}
+
+#[tokio::test]
+async fn course_names()
+{
+    let app = app();
+
+    let (status, body) = app.get("/api/kz_test").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["course_count"], 2);
+    assert_eq!(strings(&body["course_names"], "course_name"), ["main", "bonus"]);
+    assert_eq!(body["course_names"][0]["course_id"], app.main_course);
+
+    let (status, body) = app.get("/api/kz_missing").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "course_count": 0, "course_names": [] }));
+}
+
+#[tokio::test]
+async fn course_data()
+{
+    let app = app();
+
+    let (status, body) = app.get("/api/kz_reverse/main").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "course_id": app.reverse_course, "course_name": "main", "mapfk": "kz_reverse", "reverse": 1 }));
+
+    let (status, body) = app.get("/api/kz_reverse/bonus").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["code"], "not_found");
+    assert_eq!(body["error"]["message"], "course not found.");
+}
+
+#[tokio::test]
+async fn records()
+{
+    let app = app();
+
+    let (status, body) = app.get(&format!("/api/courses/{}/records", app.main_course)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["records_count"], 2);
+    assert_eq!(body["records"][0], json!({ "rank": 1, "player_name": "player 1", "steamid": player(1).to_string(), "course_time": "01:30.00", "date": "1970-01-04", "diff": "WR", "avatar": "avatar1.jpg", "country": "FI" }));
+    assert_eq!(body["records"][1]["diff"], "+00:05.50");
+
+    let (_, body) = app.get(&format!("/api/courses/{}/records?sort=date&limit=1&offset=1", app.main_course)).await;
+    assert_eq!(body["records_count"], 2);
+    assert_eq!(strings(&body["records"], "player_name"), ["player 2"]);
+
+    let (_, body) = app.get(&format!("/api/courses/{}/records?checkpoints=true", app.main_course)).await;
+    assert_eq!(strings(&body["records"], "course_time"), ["02:10.00"]);
+
+    let (_, body) = app.get(&format!("/api/courses/{}/records", app.reverse_course)).await;
+    assert_eq!(strings(&body["records"], "player_name"), ["player 2", "player 1"]);
+
+    let (status, body) = app.get("/api/courses/99/records").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body["error"]["message"].is_string());
+
+    let (status, body) = app.get(&format!("/api/courses/{}/records?sort=fastest", app.main_course)).await;
+    assert_bad_request(status, body);
+    let (status, body) = app.get("/api/courses/main/records").await;
+    assert_bad_request(status, body);
+}
+
+#[tokio::test]
+async fn legacy_records_redirect()
+{
+    let app = app();
+
+    let response = app.router.clone().oneshot(Request::get(format!("/api/records/true/kz_test/main/{}/0", app.main_course)).body(Body::empty()).unwrap()).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(response.headers()[header::LOCATION], format!("/api/courses/{}/records?checkpoints=true&limit=50&offset=0&sort=time", app.main_course).as_str());
+}
+
+#[tokio::test]
+async fn wr_history()
+{
+    let app = app();
+
+    let (status, body) = app.get(&format!("/api/courses/{}/wr-history", app.main_course)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["wrs_count"], 3);
+    assert_eq!(strings(&body["wrs"], "course_time"), ["01:30.00", "01:35.50", "01:40.00"]);
+    assert_eq!(strings(&body["wrs"], "player_name"), ["player 1", "player 2", "player 1"]);
+    assert_eq!(body["wrs"][0]["diff"], "-00:05.50");
+    assert_eq!(body["wrs"][1]["stood_seconds"], DAY);
+    assert_eq!(body["wrs"][2]["diff"], Value::Null);
+
+    let (status, _) = app.get("/api/courses/99/wr-history").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn leaderboard()
+{
+    let app = app();
+
+    let (status, body) = app.get("/api/leaderboard").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["players_count"], 2);
+    assert_eq!(strings(&body["players"], "player_name"), ["player 1", "player 2"]);
+    assert_eq!((body["players"][0]["wrs"].as_u64(), body["players"][0]["courses"].as_u64()), (Some(1), Some(2)));
+
+    let (_, body) = app.get("/api/leaderboard?checkpoints=true&limit=1").await;
+    assert_eq!(body["players_count"], 1);
+    assert_eq!(strings(&body["players"], "player_name"), ["player 2"]);
+}
+
+#[tokio::test]
+async fn country_leaderboard()
+{
+    let app = app();
+
+    let (status, body) = app.get("/api/leaderboard?country=se").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["players_count"], 1);
+    assert_eq!((body["players"][0]["rank"].as_u64(), body["players"][0]["country"].as_str()), (Some(1), Some("SE")));
+
+    let (_, body) = app.get("/api/leaderboard?country=NO").await;
+    assert_eq!(body, json!({ "players_count": 0, "players": [] }));
+
+    let (status, body) = app.get("/api/leaderboard?country=FIN").await;
+    assert_bad_request(status, body);
+}
+
+#[tokio::test]
+async fn countries()
+{
+    let app = app();
+
+    let (status, body) = app.get("/api/countries").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["countries_count"], 2);
+    assert_eq!(strings(&body["countries"], "country"), ["FI", "SE"]);
+    assert_eq!((body["countries"][0]["rank"].as_u64(), body["countries"][0]["players"].as_u64(), body["countries"][0]["wrs"].as_u64()), (Some(1), Some(1), Some(1)));
+
+    let (_, leaderboard) = app.get("/api/leaderboard").await;
+    assert_eq!(body["countries"][0]["points"], leaderboard["players"][0]["points"]);
+
+    let (_, body) = app.get("/api/countries?checkpoints=true").await;
+    assert_eq!(strings(&body["countries"], "country"), ["SE"]);
+}
+
+#[tokio::test]
+async fn recent_records()
+{
+    let app = app();
+
+    let (status, body) = app.get("/api/recent?limit=2").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["records_count"], 2);
+    assert_eq!(strings(&body["records"], "map_name"), ["kz_reverse", "kz_reverse"]);
+    assert_eq!((body["records"][0]["wr"].as_bool(), body["records"][1]["wr"].as_bool()), (Some(true), Some(true)));
+}
+
+#[tokio::test]
+async fn player_info()
+{
+    let app = app();
+
+    let (status, body) = app.get(&format!("/api/player/info/{}", player(2))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["response"]["players"][0]["steamid"], player(2).to_string());
+    assert_eq!(body["response"]["players"][0]["personaname"], "steam 2");
+
+    let (status, body) = app.get("/api/player/info/steamcommunity.com%2Fid%2Fplayer1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["response"]["players"][0]["steamid"], player(1).to_string());
+
+    let (status, _) = app.get("/api/player/info/steamcommunity.com%2Fid%2Fnobody").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = app.get("/api/player/info/STEAM_9").await;
+    assert_bad_request(status, body);
+}
+
+#[tokio::test]
+async fn player_records()
+{
+    let app = app();
+
+    let (status, body) = app.get(&format!("/api/player/records/false/{}", player(1).steamid2())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["records_count"], 2);
+    assert_eq!(strings(&body["records"], "map_name"), ["kz_reverse", "kz_test"]);
+    assert_eq!(body["records"][0]["diff"], "-01:00.00");
+    assert_eq!(body["records"][1]["diff"], "WR");
+    assert_eq!((body["records"][1]["rank"].as_u64(), body["records"][1]["field_size"].as_u64()), (Some(1), Some(2)));
+    assert_eq!(body["points"], body["records"][0]["points"].as_u64().unwrap() + body["records"][1]["points"].as_u64().unwrap());
+
+    let (_, body) = app.get("/api/player/records/true/steamcommunity.com%2Fid%2Fplayer1").await;
+    assert_eq!(body, json!({ "records_count": 0, "points": 0, "records": [] }));
+
+    let (status, body) = app.get("/api/player/records/maybe/STEAM_1:0:1").await;
+    assert_bad_request(status, body);
+}
+
+#[tokio::test]
+async fn player_history()
+{
+    let app = app();
+
+    let (status, body) = app.get(&format!("/api/player/{}/course/{}/history", player(1), app.main_course)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((body["map_name"].as_str(), body["course_name"].as_str(), body["history_count"].as_u64()), (Some("kz_test"), Some("main"), Some(2)));
+    assert_eq!(strings(&body["history"], "course_time"), ["01:40.00", "01:30.00"]);
+    assert_eq!(body["history"][0]["diff"], Value::Null);
+    assert_eq!(body["history"][1]["diff"], "-00:10.00");
+    assert_eq!(body["history"][1]["millis"], 90000);
+    assert_eq!(body["history"][1]["timestamp"], 3 * DAY);
+
+    let (status, _) = app.get(&format!("/api/player/{}/course/99/history", player(1))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn submit_record()
+{
+    let app = app();
+    let key = app.server_key.to_string();
+    let run = |time: &str, teleports: u32| json!({ "steamid": player(3).steamid2(), "player_name": "player 3", "course_id": app.main_course, "course_time": time, "checkpoints": false, "teleports": teleports });
+
+    let (status, body) = app.request(Method::POST, "/api/records", Some(&key), Some(run("00:01:32.000", 0))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "improved": true, "rank": 2, "wr": false }));
+
+    let (_, body) = app.request(Method::POST, "/api/records", Some(&key), Some(run("00:01:29.000", 0))).await;
+    assert_eq!(body, json!({ "improved": true, "rank": 1, "wr": true }));
+
+    let (_, body) = app.request(Method::POST, "/api/records", Some(&key), Some(run("00:01:31.000", 0))).await;
+    assert_eq!(body, json!({ "improved": false, "rank": 3, "wr": false }));
+
+    let (status, body) = app.request(Method::POST, "/api/records", Some(&key), Some(run("00:01:20.000", 1))).await;
+    assert_bad_request(status, body);
+    let (status, body) = app.request(Method::POST, "/api/records", Some(&key), Some(json!({ "steamid": player(3).steamid2() }))).await;
+    assert_bad_request(status, body);
+
+    let (status, _) = app.request(Method::POST, "/api/records", Some(&key), Some(json!({ "steamid": player(3).steamid2(), "player_name": "player 3", "course_id": 99, "course_time": "00:01:00.000", "checkpoints": false, "teleports": 0 }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = app.request(Method::POST, "/api/records", Some(&format!("{}.wrong", app.server_key.server_id)), Some(run("00:01:00.000", 0))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body["error"]["message"].is_string());
+
+    let (status, _) = app.request(Method::POST, "/api/records", None, Some(run("00:01:00.000", 0))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, body) = app.get(&format!("/api/courses/{}/records", app.main_course)).await;
+    assert_eq!(strings(&body["records"], "player_name"), ["player 3", "player 1", "player 2"]);
+}
+
+#[tokio::test]
+async fn admin_servers()
+{
+    let app = app();
+
+    let (status, _) = app.request(Method::GET, "/api/admin/servers", Some("wrong"), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = app.request(Method::POST, "/api/admin/servers", Some(ADMIN_KEY), Some(json!({ "name": "new server" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let server_id = body["server_id"].as_i64().unwrap();
+    let api_key = body["api_key"].as_str().unwrap().to_string();
+    assert!(api_key.starts_with(&format!("{}.", server_id)));
+
+    let (status, body) = app.request(Method::POST, "/api/admin/servers", Some(ADMIN_KEY), Some(json!({ "name": " " }))).await;
+    assert_bad_request(status, body);
+    let (status, body) = app.request(Method::DELETE, "/api/admin/servers/first", Some(ADMIN_KEY), None).await;
+    assert_bad_request(status, body);
+
+    let run = json!({ "steamid": player(3).steamid2(), "player_name": "player 3", "course_id": app.main_course, "course_time": "00:01:45.000", "checkpoints": false, "teleports": 0 });
+    let (status, _) = app.request(Method::POST, "/api/records", Some(&api_key), Some(run.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app.request(Method::DELETE, &format!("/api/admin/servers/{}", server_id), Some(ADMIN_KEY), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.request(Method::POST, "/api/records", Some(&api_key), Some(run.clone())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = app.request(Method::POST, &format!("/api/admin/servers/{}/rotate", server_id), Some(ADMIN_KEY), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.request(Method::POST, "/api/records", Some(body["api_key"].as_str().unwrap()), Some(run)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app.request(Method::GET, "/api/admin/servers", Some(ADMIN_KEY), None).await;
+    assert_eq!(body["servers_count"], 2);
+    assert_eq!(strings(&body["servers"], "name"), ["test server", "new server"]);
+    assert!(body["servers"][1]["key_rotated_at"].is_string() && body["servers"][1]["revoked_at"].is_null());
+
+    let (status, body) = app.request(Method::DELETE, &format!("/api/admin/servers/{}/records", server_id), Some(ADMIN_KEY), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "records": 1, "records_cp": 0 }));
+
+    for (method, uri) in [(Method::DELETE, "/api/admin/servers/99"), (Method::POST, "/api/admin/servers/99/rotate"), (Method::DELETE, "/api/admin/servers/99/records")]
+    {
+        let (status, _) = app.request(method, uri, Some(ADMIN_KEY), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn unknown_routes()
+{
+    let app = app();
+
+    let (status, body) = app.get("/api/courses/1/records/extra").await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["code"], "not_found");
+    assert!(body["error"]["message"].is_string());
+}
+
+#[tokio::test]
+async fn request_ids()
+{
+    let app = app();
+
+    let response = app.router.clone().oneshot(Request::get("/api/courses/99/records").body(Body::empty()).unwrap()).await.unwrap();
+    let request_id = response.headers()["x-request-id"].to_str().unwrap().to_string();
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"]["request_id"], request_id.as_str());
+
+    //NOTE: IDs set by a proxy are kept
+    let request = Request::get("/api/maps").header("x-request-id", "proxy-id-1").body(Body::empty()).unwrap();
+    let response = app.router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.headers()["x-request-id"], "proxy-id-1");
+}
@@ -0,0 +1,224 @@
+use gloo_timers::future::TimeoutFuture;
+use reqwest::{Client, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use toplist_types::
+{
+    Countries, CountriesQuery, CourseNames, ErrorCode, ErrorResponse, HistoryQuery, Leaderboard, LeaderboardQuery, Maps, Player, PlayerRecords,
+    RecentQuery, RecentRecords, RecordHistory, Records, RecordsQuery, SteamResponse, WrHistory,
+};
+
+//NOTE: Used when index.html has no toplist-api meta tag and there's no /config.json, the backend is then expected
+//behind the same origin as the frontend
+const DEFAULT_API_URL: &str = "/api";
+const CONFIG_FILE: &str = "/config.json";
+
+//NOTE: Requests that fail with a transient error are sent again after 500ms and then 1s before the error is shown
+const MAX_RETRIES: u32 = 2;
+const RETRY_DELAY_MS: u32 = 500;
+
+#[derive(Deserialize)]
+struct FrontendConfig
+{
+    api_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError
+{
+    Network(String),                //NOTE: The backend couldn't be reached
+    Status { status: u16, code: ErrorCode, message: String, request_id: Option<String> },   //NOTE: The backend answered with an error
+    Decode(String),                 //NOTE: The response didn't match toplist-types, usually a backend of another version
+}
+
+impl ApiError
+{
+    //NOTE: Worth another try, the backend is restarting, overloaded or behind a proxy that timed out
+    pub fn is_transient(&self) -> bool
+    {
+        match self
+        {
+            ApiError::Network(_) => true,
+            ApiError::Status { status, .. } => matches!(*status, 429 | 502 | 503 | 504),
+            ApiError::Decode(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ApiError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            ApiError::Network(_) => write!(f, "Unable to reach the toplist, check your connection and try again."),
+            ApiError::Status { code: ErrorCode::RateLimited, .. } | ApiError::Status { status: 429, .. } => write!(f, "Too many requests, try again in a moment."),
+            ApiError::Status { status, message, .. } if *status < 500 => write!(f, "{}", message),
+            //NOTE: The request ID is what to look for in the backend's logs when the problem is reported
+            ApiError::Status { message, request_id: Some(request_id), .. } => write!(f, "The toplist is having problems: {} (request {})", message, request_id),
+            ApiError::Status { message, request_id: None, .. } => write!(f, "The toplist is having problems: {}", message),
+            ApiError::Decode(_) => write!(f, "The toplist sent something unexpected, try reloading the page."),
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError
+{
+    fn from(e: reqwest::Error) -> Self
+    {
+        if e.is_decode()
+        {
+            ApiError::Decode(e.to_string())
+        }
+        else
+        {
+            ApiError::Network(e.to_string())
+        }
+    }
+}
+
+//NOTE: Shared by every page through the context provided in main, one method per route the pages load
+#[derive(Clone, Debug)]
+pub struct ApiClient
+{
+    client: Client,
+    base_url: String,
+}
+
+impl ApiClient
+{
+    //NOTE: The base can be relative to the origin the frontend is served from, like /api, or a full URL
+    pub fn new(base_url: &str) -> Self
+    {
+        let base_url = match origin().and_then(|origin| origin.join(base_url).ok())
+        {
+            Some(url) => url.to_string(),
+            None => base_url.to_string(),
+        };
+        ApiClient { client: Client::new(), base_url: base_url.trim_end_matches('/').to_string() }
+    }
+
+    //NOTE: Looks for <meta name="toplist-api" content="..."> first so the base can be set without an extra request,
+    //then for the api_url in /config.json
+    pub async fn load() -> Self
+    {
+        if let Some(base_url) = meta_api_url()
+        {
+            return ApiClient::new(&base_url);
+        }
+        if let Some(base_url) = config_api_url().await
+        {
+            return ApiClient::new(&base_url);
+        }
+        ApiClient::new(DEFAULT_API_URL)
+    }
+
+    pub async fn maps(&self) -> Result<Maps, ApiError>
+    {
+        self.get("/maps", &()).await
+    }
+
+    pub async fn course_names(&self, map_name: &str) -> Result<CourseNames, ApiError>
+    {
+        self.get(&format!("/{}", map_name), &()).await
+    }
+
+    pub async fn records(&self, course_id: i32, query: &RecordsQuery) -> Result<Records, ApiError>
+    {
+        self.get(&format!("/courses/{}/records", course_id), query).await
+    }
+
+    pub async fn wr_history(&self, course_id: i32, query: &HistoryQuery) -> Result<WrHistory, ApiError>
+    {
+        self.get(&format!("/courses/{}/wr-history", course_id), query).await
+    }
+
+    pub async fn recent_records(&self, query: &RecentQuery) -> Result<RecentRecords, ApiError>
+    {
+        self.get("/recent", query).await
+    }
+
+    pub async fn leaderboard(&self, query: &LeaderboardQuery) -> Result<Leaderboard, ApiError>
+    {
+        self.get("/leaderboard", query).await
+    }
+
+    pub async fn countries(&self, query: &CountriesQuery) -> Result<Countries, ApiError>
+    {
+        self.get("/countries", query).await
+    }
+
+    //NOTE: None when Steam doesn't know the player
+    pub async fn player_info(&self, steamid: &str) -> Result<Option<Player>, ApiError>
+    {
+        let response: SteamResponse = self.get(&format!("/player/info/{}", steamid), &()).await?;
+        Ok(response.response.players.into_iter().next())
+    }
+
+    pub async fn player_records(&self, steamid: &str, checkpoints: bool) -> Result<PlayerRecords, ApiError>
+    {
+        self.get(&format!("/player/records/{}/{}", checkpoints, steamid), &()).await
+    }
+
+    pub async fn player_history(&self, steamid: &str, course_id: i32, query: &HistoryQuery) -> Result<RecordHistory, ApiError>
+    {
+        self.get(&format!("/player/{}/course/{}/history", steamid, course_id), query).await
+    }
+
+    async fn get<T: DeserializeOwned, Q: Serialize + ?Sized>(&self, path: &str, query: &Q) -> Result<T, ApiError>
+    {
+        let mut attempt = 0;
+        loop
+        {
+            match self.get_once(path, query).await
+            {
+                Err(e) if e.is_transient() && attempt < MAX_RETRIES =>
+                {
+                    TimeoutFuture::new(RETRY_DELAY_MS << attempt).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn get_once<T: DeserializeOwned, Q: Serialize + ?Sized>(&self, path: &str, query: &Q) -> Result<T, ApiError>
+    {
+        let response = self.client.get(format!("{}{}", self.base_url, path)).query(query).send().await?;
+        let status = response.status();
+        if status.is_success()
+        {
+            return Ok(response.json::<T>().await?);
+        }
+
+        //NOTE: Proxies in front of the backend answer with HTML, the status text has to do then
+        let reason = status.canonical_reason().unwrap_or("Unknown error");
+        let status = status.as_u16();
+        match response.json::<ErrorResponse>().await
+        {
+            Ok(body) => Err(ApiError::Status { status, code: body.error.code, message: body.error.message, request_id: body.error.request_id }),
+            Err(_) => Err(ApiError::Status { status, code: ErrorCode::Unknown, message: reason.to_string(), request_id: None }),
+        }
+    }
+}
+
+fn origin() -> Option<Url>
+{
+    let origin = web_sys::window()?.location().origin().ok()?;
+    Url::parse(&origin).ok()
+}
+
+fn meta_api_url() -> Option<String>
+{
+    let meta = web_sys::window()?.document()?.query_selector("meta[name=toplist-api]").ok()??;
+    meta.get_attribute("content").filter(|content| !content.trim().is_empty())
+}
+
+//NOTE: A missing file is the same as an empty one, dev servers that answer every path with index.html included
+async fn config_api_url() -> Option<String>
+{
+    let url = origin()?.join(CONFIG_FILE).ok()?;
+    let response = reqwest::get(url).await.ok()?.error_for_status().ok()?;
+    response.json::<FrontendConfig>().await.ok()?.api_url.filter(|api_url| !api_url.trim().is_empty())
+}
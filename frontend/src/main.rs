@@ -1,3 +1,4 @@
+mod api;
 mod pages;
 
 use sycamore::prelude::*;
@@ -13,6 +14,10 @@ enum AppRoutes
     Records { map_name: String },
     #[to("/profile/<steamid>")]
     Profile { steamid: String },
+    #[to("/profile/<steamid>/history/<course_id>/<checkpoints>")]
+    History { steamid: String, course_id: i32, checkpoints: bool },
+    #[to("/leaderboard")]
+    Leaderboard,
     #[not_found]
     NotFound,
 }
@@ -28,6 +33,8 @@ async fn Switch<'a, G: Html>(cx: Scope<'a>, route: &'a ReadSignal<AppRoutes>) ->
             AppRoutes::Maps => view! { cx, pages::maps::MapList() },
             AppRoutes::Records { map_name } => view! { cx, pages::records::RecordsPage(map_name=map_name.clone()) },
             AppRoutes::Profile {steamid } => view! { cx, pages::profile::ProfilePage(steamid=steamid.clone()) },
+            AppRoutes::History { steamid, course_id, checkpoints } => view! { cx, pages::history::HistoryPage(steamid=steamid.clone(), course_id=*course_id, checkpoints=*checkpoints) },
+            AppRoutes::Leaderboard => view! { cx, pages::leaderboard::LeaderboardPage() },
             AppRoutes::NotFound => view! { cx, "404 Page Not Found"}
         })
     }
@@ -39,6 +46,7 @@ fn App<G: Html>(cx: Scope) -> View<G>
     view!
     {
         cx,
+        pages::ErrorBanner {}
         Router(
             integration=HistoryIntegration::new(),
             view=|cx: Scope, route: &ReadSignal<AppRoutes>| view!
@@ -52,13 +60,20 @@ fn App<G: Html>(cx: Scope) -> View<G>
 
 fn main()
 {
-    sycamore::render(|cx| 
+    //NOTE: The API base has to be known before the first page loads its data
+    wasm_bindgen_futures::spawn_local(async
     {
-        view!
+        let api = api::ApiClient::load().await;
+        sycamore::render(|cx|
         {
-            cx,
-            App {}
-        }
+            provide_context(cx, api);
+            provide_context(cx, create_rc_signal(None::<api::ApiError>));
+            view!
+            {
+                cx,
+                App {}
+            }
+        });
     });
 }
 
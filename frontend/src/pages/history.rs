@@ -0,0 +1,191 @@
+use crate::api::ApiClient;
+use crate::pages::unwrap_or_report;
+use sycamore::prelude::*;
+use sycamore::futures::spawn_local_scoped;
+use sycamore::suspense::Suspense;
+use toplist_types::{HistoryEntry, HistoryQuery, RecordHistory};
+
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 300.0;
+const CHART_PADDING: f64 = 20.0;
+
+//NOTE: Maps each personal best to a point in the chart, date on the x axis and time on the y axis with the slowest time at the top.
+//A single time or several times set at once are centered on that axis
+fn chart_points(history: &[HistoryEntry]) -> Vec<(f64, f64)>
+{
+    let min_timestamp = history.iter().map(|entry| entry.timestamp).min().unwrap_or_default();
+    let max_timestamp = history.iter().map(|entry| entry.timestamp).max().unwrap_or_default();
+    let min_millis = history.iter().map(|entry| entry.millis).min().unwrap_or_default();
+    let max_millis = history.iter().map(|entry| entry.millis).max().unwrap_or_default();
+
+    let scale = |value: f64, min: f64, max: f64, length: f64| if max > min { (value - min) / (max - min) * length } else { length / 2.0 };
+
+    history.iter().map(|entry|
+    {
+        let x = CHART_PADDING + scale(entry.timestamp as f64, min_timestamp as f64, max_timestamp as f64, CHART_WIDTH - 2.0 * CHART_PADDING);
+        let y = CHART_PADDING + scale((max_millis - entry.millis) as f64, 0.0, (max_millis - min_millis) as f64, CHART_HEIGHT - 2.0 * CHART_PADDING);
+        (x, y)
+    }).collect()
+}
+
+#[component]
+async fn HistoryChart<G: Html>(cx: Scope<'_>) -> View<G>
+{
+    let history = use_context::<RcSignal<RecordHistory>>(cx);
+
+    view!
+    {
+        cx,
+        (
+            {
+                let points = chart_points(&history.get().history);
+                let line = points.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect::<Vec<String>>().join(" ");
+                let dots = View::new_fragment(points.into_iter().map(|(x, y)|
+                {
+                    view!
+                    {
+                        cx,
+                        circle(cx=format!("{:.1}", x), cy=format!("{:.1}", y), r="4", class="fill-primary") {}
+                    }
+                }).collect());
+
+                view!
+                {
+                    cx,
+                    svg(viewBox=format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT), class="w-full bg-secondary")
+                    {
+                        line(x1=CHART_PADDING.to_string(), y1=(CHART_HEIGHT - CHART_PADDING).to_string(), x2=(CHART_WIDTH - CHART_PADDING).to_string(), y2=(CHART_HEIGHT - CHART_PADDING).to_string(), class="stroke-gray-600") {}
+                        line(x1=CHART_PADDING.to_string(), y1=CHART_PADDING.to_string(), x2=CHART_PADDING.to_string(), y2=(CHART_HEIGHT - CHART_PADDING).to_string(), class="stroke-gray-600") {}
+                        polyline(points=line, fill="none", class="stroke-primary", style="stroke-width: 2") {}
+                        (dots)
+                    }
+                }
+            }
+        )
+    }
+}
+
+#[component]
+async fn HistoryComponent<G: Html>(cx: Scope<'_>) -> View<G>
+{
+    let history = use_context::<RcSignal<RecordHistory>>(cx);
+
+    view!
+    {
+        cx,
+        (
+            {
+                let views = View::new_fragment(history.get().history.clone().into_iter().rev().enumerate().map(|(i, entry)|
+                {
+                    let i = i + 1;
+
+                    //NOTE: Alternating background for each row
+                    let bg = if i % 2 == 0
+                    {
+                        "bg-ternary"
+                    }
+                    else
+                    {
+                        "bg-secondary"
+                    };
+
+                    view!
+                    {
+                        cx,
+                        tr(class=bg)
+                        {
+                            td(class="text-sm xl:text-base 3xl:text-xl text-gray-400 font-bold px-6 py-4 whitespace-nowrap")
+                            {
+                                (entry.date.clone().unwrap_or_default())
+                            }
+                            td(class="text-sm xl:text-base 3xl:text-xl text-gray-400 font-bold px-6 py-4 whitespace-nowrap")
+                            {
+                                (entry.course_time)
+                            }
+                            td(class="text-sm xl:text-base 3xl:text-xl text-gray-400 font-bold px-6 py-4 whitespace-nowrap")
+                            {
+                                (entry.diff.map(|diff| diff.to_string()).unwrap_or_default())
+                            }
+                        }
+                    }
+                }).collect());
+
+                view!
+                {
+                    cx,
+                    (views)
+                }
+            }
+        )
+    }
+}
+
+#[component(inline_props)]
+pub fn HistoryPage<G: Html>(cx: Scope, steamid: String, course_id: i32, checkpoints: bool) -> View<G>
+{
+    let api = use_context::<ApiClient>(cx);
+    provide_context(cx, create_rc_signal(RecordHistory::default()));
+    let history = use_context::<RcSignal<RecordHistory>>(cx);
+    let profile_url = format!("/profile/{}", steamid);
+
+    spawn_local_scoped(cx, async move
+    {
+        history.set(unwrap_or_report(cx, api.player_history(&steamid, course_id, &HistoryQuery { checkpoints }).await));
+    });
+
+    view!
+    {
+        cx,
+        Suspense(fallback=view! { cx, })
+        {
+            h1(class="mb-0 2xl:mb-6 text-4xl font-bold pt-12 pb-6 bg-background text-center text-primary")
+            {
+                (history.get().map_name.clone()) " " (history.get().course_name.clone())
+            }
+            div(class="scale-75 lg:scale-90 xl:scale-100 flex justify-center items-start gap-4")
+            {
+                div(class="shadow-2xl h-full text-center text-sm bg-card p-4 sticky top-0 items-start")
+                {
+                    a(href=profile_url)
+                    {
+                        p(class="text-primary font-bold uppercase pt-2 text-base hover:scale-110 duration-150") { "Back to profile" }
+                    }
+                    p(class="text-primary font-bold uppercase pt-2 text-base")
+                    {
+                        (if checkpoints { "Checkpoints" } else { "Pro" })
+                    }
+                    p(class="text-primary font-bold uppercase pt-2 text-base")
+                    {
+                        "Personal bests (" (history.get().history_count) ")"
+                    }
+                }
+                div(class="w-[800px] max-w-[1140px] flex flex-col gap-4")
+                {
+                    div(class="shadow-2xl")
+                    {
+                        HistoryChart {}
+                    }
+                    div(class="shadow-2xl overflow-x-auto")
+                    {
+                        table(class="rounded-lg min-w-full")
+                        {
+                            thead(class="bg-primary")
+                            {
+                                tr
+                                {
+                                    th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-6 py-4 text-left"){"Date"}
+                                    th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-6 py-4 text-left"){"Time"}
+                                    th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-6 py-4 text-left"){"Improvement"}
+                                }
+                            }
+                            tbody
+                            {
+                                HistoryComponent {}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
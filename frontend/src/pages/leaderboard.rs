@@ -0,0 +1,190 @@
+use crate::api::ApiClient;
+use crate::pages::{unwrap_or_report, PlayerIcons};
+use sycamore::prelude::*;
+use sycamore::futures::spawn_local_scoped;
+use sycamore::suspense::Suspense;
+use toplist_types::{CountriesQuery, LeaderboardEntry, LeaderboardQuery};
+use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+
+#[component]
+async fn CountryFilterComponent<G: Html>(cx: Scope<'_>) -> View<G>
+{
+    let api = use_context::<ApiClient>(cx);
+    let country = use_context::<RcSignal<String>>(cx);
+    let countries = unwrap_or_report(cx, api.countries(&CountriesQuery::default()).await).countries;
+
+    let options = View::new_fragment(countries.into_iter().map(|entry|
+    {
+        let code = entry.country.clone();
+        view!
+        {
+            cx,
+            option(value=code) { (entry.country) " (" (entry.players) ")" }
+        }
+    }).collect());
+
+    view!
+    {
+        cx,
+        select(on:change=move |event: web_sys::Event| country.set(event.target().unwrap().unchecked_into::<HtmlSelectElement>().value()), class="w-full font-bold text-primary bg-transparent border-2 border-solid border-primary px-2 py-2 hover:cursor-pointer")
+        {
+            option(value="") { "All countries" }
+            (options)
+        }
+    }
+}
+
+#[component]
+async fn LeaderboardComponent<G: Html>(cx: Scope<'_>) -> View<G>
+{
+    let api = use_context::<ApiClient>(cx);
+    let cp_signal = use_context::<RcSignal<bool>>(cx);
+    let country = use_context::<RcSignal<String>>(cx);
+    let players = create_signal(cx, Vec::<LeaderboardEntry>::new());
+
+    create_effect(cx, move ||
+    {
+        cp_signal.track();
+        country.track();
+        spawn_local_scoped(cx, async move
+        {
+            //NOTE: An empty country ranks every player
+            let country = Some(country.get().as_ref().clone()).filter(|country| !country.is_empty());
+            let query = LeaderboardQuery { checkpoints: *cp_signal.get(), country, ..Default::default() };
+            players.set(unwrap_or_report(cx, api.leaderboard(&query).await).players);
+        });
+    });
+
+    view!
+    {
+        cx,
+        (
+            {
+                let views = View::new_fragment(players.get().as_ref().clone().into_iter().enumerate().map(|(i, player)|
+                {
+                    let i = i + 1;
+
+                    //NOTE: Alternating background for each row
+                    let bg = if i % 2 == 0
+                    {
+                        "bg-ternary"
+                    }
+                    else
+                    {
+                        "bg-secondary"
+                    };
+
+                    let steamid = player.steamid;
+
+                    view!
+                    {
+                        cx,
+                        tr(class=bg)
+                        {
+                            td(class="px-8 py-4 whitespace-nowrap text-sm xl:text-base 3xl:text-xl font-bold text-white")
+                            {
+                                (player.rank)
+                            }
+                            a(href=format!("/profile/{}", steamid))
+                            {
+                                td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden hover:scale-110 hover:text-primary duration-150 hover:cursor-pointer text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
+                                {
+                                    PlayerIcons(avatar=player.avatar.clone(), country=player.country.clone())
+                                    (player.player_name)
+                                }
+                            }
+                            td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4 text-right")
+                            {
+                                (player.points)
+                            }
+                            td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4 text-right")
+                            {
+                                (player.wrs)
+                            }
+                            td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4 text-right")
+                            {
+                                (player.top10)
+                            }
+                            td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4 text-right")
+                            {
+                                (player.courses)
+                            }
+                        }
+                    }
+                }).collect());
+
+                view!
+                {
+                    cx,
+                    (views)
+                }
+            }
+        )
+    }
+}
+
+#[component]
+pub fn LeaderboardPage<G: Html>(cx: Scope) -> View<G>
+{
+    let cp_signal = create_rc_signal(false);
+    provide_context(cx, cp_signal.clone());
+    provide_context(cx, create_rc_signal(String::new()));
+
+    view!
+    {
+        cx,
+        Suspense(fallback=view! { cx, } )
+        {
+            h1(class="mb-0 2xl:mb-6 text-4xl font-bold pt-12 pb-6 bg-background text-center text-primary") { "Leaderboard" }
+            div(class="scale-75 lg:scale-90 xl:scale-100 flex justify-center items-start gap-4")
+            {
+                div(class="shadow-2xl h-full text-center text-sm bg-card p-4 sticky top-0 items-start")
+                {
+                    label(class="ml-0 mt-6 mb-6 mr-6 relative inline-flex items-center cursor-pointer")
+                    {
+                        input(on:change=move |_| cp_signal.set(!*cp_signal.get()), type="checkbox", value="", class="sr-only peer") {}
+                        div(class="w-11 h-6 bg-gray-300 rounded-full peer-checked:after:translate-x-full peer-checked:after:border-white after:content-[''] after:absolute after:top-0.5 after:left-[2px] after:bg-white after:border-gray-300 after:border after:rounded-full after:h-5 after:w-5 after:transition-all peer-checked:bg-primary") {}
+                        span(class="ml-3 text-sm font-bold text-primary") { "Checkpoints" }
+                    }
+                    CountryFilterComponent {}
+                }
+                div(class="max-w-[1140px] flex flex-col")
+                {
+                    div(class="shadow-2xl overflow-x-auto")
+                    {
+                        div(class="inline-block min-w-full")
+                        {
+                            div(class="overflow-hidden")
+                            {
+                                table(class="rounded-lg min-w-full")
+                                {
+                                    thead(class="bg-primary")
+                                    {
+                                        tr
+                                        {
+                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"#"}
+                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Player"}
+                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Points"}
+                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"WRs"}
+                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Top 10"}
+                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Courses"}
+                                        }
+                                    }
+                                    tbody
+                                    {
+                                        LeaderboardComponent {}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    p(class="text-right text-sm font-bold text-primary pt-2 pb-4")
+                    {
+                        "Toplist by Menko and GoldenNinja"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,34 +1,17 @@
-use crate::pages::BASE_API_URL;
+use crate::api::ApiClient;
+use crate::pages::unwrap_or_report;
+use sycamore::futures::spawn_local_scoped;
 use sycamore::suspense::Suspense;
 use sycamore::prelude::*;
-use serde::{Serialize, Deserialize};
+use toplist_types::{RecentQuery, RecentRecord};
 
-//NOTE: Structs copied over from the backend
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
-struct Maps
-{
-    maps: Vec<Map>
-}
-
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-struct Map
-{
-    map_name: String,
-    times_played: i32,
-    time_added: String
-}
-
-async fn get_maps() -> Result<Maps, reqwest::Error>
-{
-    let url = format!("{}/maps", BASE_API_URL);
-    let request = reqwest::get(&url).await?.json::<Maps>().await?;
-    Ok(request)
-}
+const RECENT_RECORDS: u32 = 10;
 
 #[component]
 async fn MapListComponent<G: Html>(cx: Scope<'_>) -> View<G>
 {
-    let data = get_maps().await.unwrap_or_default();
+    let api = use_context::<ApiClient>(cx);
+    let data = unwrap_or_report(cx, api.maps().await);
 
     let views = View::new_fragment(data.maps.into_iter().skip(1).enumerate().map(|(i, map)| 
     {   
@@ -65,11 +48,11 @@ async fn MapListComponent<G: Html>(cx: Scope<'_>) -> View<G>
                 }
                 td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden hover:scale-110 hover:text-primary duration-150 hover:cursor-pointer text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
                 {
-                    (map.time_added)
+                    (map.time_added.clone().unwrap_or_default())
                 }
                 td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden hover:scale-110 hover:text-primary duration-150 hover:cursor-pointer text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4 text-right")
                 {
-                    (map.times_played)
+                    (map.times_played.unwrap_or_default())
                 }
             }
         }
@@ -82,17 +65,103 @@ async fn MapListComponent<G: Html>(cx: Scope<'_>) -> View<G>
     }
 }
 
+#[component]
+async fn RecentRecordsComponent<G: Html>(cx: Scope<'_>) -> View<G>
+{
+    let api = use_context::<ApiClient>(cx);
+    let cp_signal = use_context::<RcSignal<bool>>(cx);
+    let records = create_signal(cx, Vec::<RecentRecord>::new());
+
+    create_effect(cx, move ||
+    {
+        cp_signal.track();
+        spawn_local_scoped(cx, async move
+        {
+            let query = RecentQuery { checkpoints: *cp_signal.get(), limit: RECENT_RECORDS };
+            records.set(unwrap_or_report(cx, api.recent_records(&query).await).records);
+        });
+    });
+
+    view!
+    {
+        cx,
+        (
+            {
+                let views = View::new_fragment(records.get().as_ref().clone().into_iter().map(|record|
+                {
+                    let map_name = record.map_name.clone();
+                    let steamid = record.steamid;
+
+                    view!
+                    {
+                        cx,
+                        div(class="text-left border-b-2 border-solid border-ternary py-2")
+                        {
+                            a(href=format!("/map/{}", map_name))
+                            {
+                                p(class="font-bold text-gray-400 hover:text-primary duration-150 text-ellipsis overflow-hidden whitespace-nowrap")
+                                {
+                                    (record.map_name) " " (record.course_name)
+                                }
+                            }
+                            a(href=format!("/profile/{}", steamid))
+                            {
+                                p(class="font-bold text-white hover:text-primary duration-150 text-ellipsis overflow-hidden whitespace-nowrap")
+                                {
+                                    (record.player_name)
+                                }
+                            }
+                            p(class="font-bold text-gray-400")
+                            {
+                                (record.course_time) " " (record.date.clone().unwrap_or_default()) " "
+                                span(class="text-primary")
+                                {
+                                    (if record.wr { "WR" } else { "" })
+                                }
+                            }
+                        }
+                    }
+                }).collect());
+
+                view!
+                {
+                    cx,
+                    (views)
+                }
+            }
+        )
+    }
+}
+
 #[component]
 pub fn MapList<G: Html>(cx: Scope) -> View<G>
 {
+    let cp_signal = create_rc_signal(false);
+    provide_context(cx, cp_signal.clone());
+
     view!
     {
         cx,
         Suspense(fallback=view! {cx, } )
         {
             h1(class="mb-0 2xl:mb-6 text-4xl font-bold pt-12 pb-6 bg-background text-center text-primary") { "Maps" }
+            a(href="/leaderboard")
+            {
+                p(class="text-center text-sm font-bold text-primary hover:scale-110 duration-150 pb-6") { "Leaderboard" }
+            }
             div(class="scale-75 lg:scale-90 xl:scale-100 flex justify-center items-start gap-4")
             {
+                div(class="shadow-2xl h-full text-center text-sm bg-card p-4 sticky top-0 items-start w-[300px]")
+                {
+                    p(class="text-primary font-bold uppercase text-base") { "Latest records" }
+                    label(class="ml-0 mt-4 mb-4 relative inline-flex items-center cursor-pointer")
+                    {
+                        input(on:change=move |_| cp_signal.set(!*cp_signal.get()), type="checkbox", value="", class="sr-only peer") {}
+                        div(class="w-11 h-6 bg-gray-300 rounded-full peer-checked:after:translate-x-full peer-checked:after:border-white after:content-[''] after:absolute after:top-0.5 after:left-[2px] after:bg-white after:border-gray-300 after:border after:rounded-full after:h-5 after:w-5 after:transition-all peer-checked:bg-primary") {}
+                        span(class="ml-3 text-sm font-bold text-primary") { "Checkpoints" }
+                    }
+                    RecentRecordsComponent {}
+                }
                 div(class="max-w-[1140px] flex flex-col")
                 {
                     div(class="shadow-2xl overflow-x-auto")
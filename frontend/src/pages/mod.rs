@@ -1,4 +1,87 @@
-pub static BASE_API_URL: &str = "http://localhost:3000/api";
+use crate::api::ApiError;
+use sycamore::prelude::*;
+
 pub mod maps;
+pub mod leaderboard;
+pub mod history;
 pub mod records;
 pub mod profile;
+
+//NOTE: Steam avatar and country flag shown in front of player names. Both are left out until the backend has stored the
+//player's Steam profile, flags come from the flag-icons stylesheet in index.html
+#[component(inline_props)]
+pub fn PlayerIcons<G: Html>(cx: Scope, avatar: Option<String>, country: Option<String>) -> View<G>
+{
+    let avatar = match avatar
+    {
+        Some(avatar) => view! { cx, img(src=avatar, alt="", class="inline-block align-middle w-6 h-6 mr-3 rounded-sm") },
+        None => view! { cx, },
+    };
+    let flag = match country
+    {
+        Some(country) =>
+        {
+            let class = format!("fi fi-{} align-middle mr-3", country.to_lowercase());
+            view! { cx, span(class=class, title=country) }
+        }
+        None => view! { cx, },
+    };
+
+    view!
+    {
+        cx,
+        (avatar)
+        (flag)
+    }
+}
+
+//NOTE: Pages keep showing what they have when a request fails, the error goes to the ErrorBanner instead
+pub fn unwrap_or_report<T: Default>(cx: Scope, result: Result<T, ApiError>) -> T
+{
+    match result
+    {
+        Ok(value) => value,
+        Err(e) =>
+        {
+            use_context::<RcSignal<Option<ApiError>>>(cx).set(Some(e));
+            T::default()
+        }
+    }
+}
+
+//NOTE: Shows the last failed request on top of every page until it's dismissed, retrying reloads the page so every
+//request is sent again
+#[component]
+pub fn ErrorBanner<G: Html>(cx: Scope) -> View<G>
+{
+    let error = use_context::<RcSignal<Option<ApiError>>>(cx);
+
+    view!
+    {
+        cx,
+        (match error.get().as_ref()
+        {
+            Some(e) =>
+            {
+                let message = e.to_string();
+                view!
+                {
+                    cx,
+                    div(class="sticky top-0 w-full flex justify-center items-center gap-4 bg-card shadow-2xl py-4 text-sm font-bold text-white")
+                    {
+                        span { (message) }
+                        button(on:click=|_|
+                        {
+                            if let Some(window) = web_sys::window()
+                            {
+                                let _ = window.location().reload();
+                            }
+                        }, class="text-primary border-2 border-solid border-primary px-2 hover:bg-primary hover:text-secondary hover:cursor-pointer") { "Retry" }
+                        button(on:click=|_| error.set(None), class="text-gray-400 px-2 hover:text-primary hover:cursor-pointer") { "Dismiss" }
+                    }
+                }
+            }
+            None => view! { cx, },
+        })
+    }
+}
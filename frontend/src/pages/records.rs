@@ -1,95 +1,49 @@
-use crate::pages::BASE_API_URL;
+use crate::api::ApiClient;
+use crate::pages::{unwrap_or_report, PlayerIcons};
 use sycamore::futures::spawn_local_scoped;
 use sycamore::suspense::Suspense;
 use sycamore::prelude::*;
-use serde::{Serialize, Deserialize};
+use toplist_types::{HistoryQuery, Records, RecordsQuery, WrEntry};
 use web_sys::HtmlElement;
 
-//NOTE: Structs copied over from the backend
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
-struct CourseNames
-{
-    course_count: usize,
-    course_names: Vec<CourseName>,
-}
-
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-struct CourseName
-{
-    course_name: String,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
-struct Courses
-{
-    course_count: usize,
-    courses: Vec<Course>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
-struct Course
-{
-    course_id: i32,
-    course_name: String,
-    mapfk: String,
-    reverse: i8,
-}
+const RECORDS_PER_PAGE: u32 = 50;
 
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
-struct Records
+//NOTE: Which table is shown next to the course list
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecordsTab
 {
-    records_count: usize,
-    records: Vec<Record>,
+    Records,
+    WrHistory,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-struct Record
+fn format_stood(seconds: i64) -> String
 {
-    player_name: String,
-    steamid: String,
-    course_time: String,
-    date: String,
-    diff: String,
-}
-
-async fn get_course_names(map_name: &str) -> Result<CourseNames, reqwest::Error>
-{
-    let url = format!("{}/{}", BASE_API_URL, map_name);
-    let request = reqwest::get(&url).await?.json::<CourseNames>().await?;
-    Ok(request)
-}
-
-async fn get_course_data(map_name: &str, course_name: &str) -> Result<Course, reqwest::Error>
-{
-    let url = format!("{}/{}/{}", BASE_API_URL, map_name, course_name);
-    let request = reqwest::get(&url).await?.json::<Course>().await?;
-    Ok(request)
-}
-
-async fn get_records(checkpoints: bool, map_name: &str, course_name: &str, courseid: i32, rflag: i8) -> Result<Records, reqwest::Error>
-{
-    let url = format!("{}/records/{}/{}/{}/{}/{}", BASE_API_URL, checkpoints, map_name, course_name, courseid, rflag);
-    let request = reqwest::get(&url).await?.json::<Records>().await?;
-    Ok(request)
+    match seconds / (24 * 60 * 60)
+    {
+        0 => String::from("< 1 day"),
+        1 => String::from("1 day"),
+        days => format!("{} days", days),
+    }
 }
 
 #[component(inline_props)]
 async fn CoursesComponent<'a, G: Html>(cx: BoundedScope<'a, 'a>, map_name: String) -> View<G>
 {
-    let state = use_context::<RcSignal<String>>(cx);
-    let data = get_course_names(&map_name).await.unwrap_or_default();
+    let api = use_context::<ApiClient>(cx);
+    let state = use_context::<RcSignal<i32>>(cx);
+    let data = unwrap_or_report(cx, api.course_names(&map_name).await);
     let first = create_node_ref(cx);
 
     let views = View::new_fragment(data.course_names.into_iter().enumerate().map(|(i, course)| 
     {   
-        let course_name = course.course_name.clone();
+        let course_id = course.course_id;
 
         if i == 0
         {
             view!
             {
                 cx,
-                div(ref=first, on:click=move |_| state.set(course_name.clone()), class="font-bold text-primary hover:scale-110 hover:bg-primary hover:text-secondary duration-150 hover:cursor-pointer bg-transparent border-2 border-solid border-primary px-2 py-4")
+                div(ref=first, on:click=move |_| state.set(course_id), class="font-bold text-primary hover:scale-110 hover:bg-primary hover:text-secondary duration-150 hover:cursor-pointer bg-transparent border-2 border-solid border-primary px-2 py-4")
                 {
                         (course.course_name)
                 }
@@ -100,7 +54,7 @@ async fn CoursesComponent<'a, G: Html>(cx: BoundedScope<'a, 'a>, map_name: Strin
             view!
             {
                 cx,
-                div(on:click=move |_| state.set(course_name.clone()), class="font-bold text-primary hover:scale-110 hover:bg-primary hover:text-secondary duration-150 hover:cursor-pointer bg-transparent border-2 border-solid border-primary px-2 py-4")
+                div(on:click=move |_| state.set(course_id), class="font-bold text-primary hover:scale-110 hover:bg-primary hover:text-secondary duration-150 hover:cursor-pointer bg-transparent border-2 border-solid border-primary px-2 py-4")
                 {
                     (course.course_name)
                 } 
@@ -108,7 +62,11 @@ async fn CoursesComponent<'a, G: Html>(cx: BoundedScope<'a, 'a>, map_name: Strin
         }
     }).collect());
 
-    first.get::<DomNode>().unchecked_into::<HtmlElement>().click();
+    //NOTE: Maps without courses, or whose courses couldn't be loaded, have nothing to select
+    if let Some(first) = first.try_get::<DomNode>()
+    {
+        first.unchecked_into::<HtmlElement>().click();
+    }
 
     view!
     {
@@ -117,39 +75,55 @@ async fn CoursesComponent<'a, G: Html>(cx: BoundedScope<'a, 'a>, map_name: Strin
     }
 }
 
-#[component(inline_props)]
-async fn RecordsComponent<G: Html>(cx: Scope<'_>, map_name: String) -> View<G>
+#[component]
+async fn RecordsComponent<G: Html>(cx: Scope<'_>) -> View<G>
 {
-    let state = use_context::<RcSignal<String>>(cx);
+    let api = use_context::<ApiClient>(cx);
+    let state = use_context::<RcSignal<i32>>(cx);
     let checkpoints = use_context::<RcSignal<bool>>(cx);
-    let course_data = create_signal(cx, Course { course_id: -1, course_name: String::from(""), mapfk: String::from(""), reverse: -1 });
-    let data = create_signal(cx, Records {records_count:0, records: vec![Record { player_name: String::from(""), steamid: String::from(""), course_time: String::from(""), date: String::from(""), diff: String::from("") }]});
+    let page = use_context::<RcSignal<u32>>(cx);
+    let records_count = use_context::<RcSignal<usize>>(cx);
+    let data = create_signal(cx, Records::default());
     let records = create_signal(cx, data.get().records.clone());
 
+    //NOTE: Go back to the first page whenever a different course or record type is picked. Setting the page always
+    //notifies, so the effect below loads the records even when the first page was shown already
     create_effect(cx, move ||
     {
-        let map = map_name.clone(); //NOTE: needed due to move in spawn_local_scoped
-        state.track(); //NOTE: we need to explicitly track reactive variable or it won't be tracked inside spawn_local_scoped
-        spawn_local_scoped(cx, async move
-        {
-            if !state.get().is_empty()
-            {
-                course_data.set(get_course_data(&map, &state.get().as_ref().clone()).await.unwrap_or_default());
-            }
-        });
+        state.track();
+        checkpoints.track();
+        page.set(0);
     });
 
+    //NOTE: Only the page is tracked, a different course or record type comes through the reset above so it's loaded once
     create_effect(cx, move ||
     {
-        course_data.track();
-        checkpoints.track();
+        page.track();
+        let (course_id, cp, page_number) = (*state.get_untracked(), *checkpoints.get_untracked(), *page.get_untracked());
+        if course_id == -1
+        {
+            return;
+        }
+
         spawn_local_scoped(cx, async move
         {
-            if course_data.get().course_id != -1
+            let query = RecordsQuery
             {
-                data.set(get_records(*checkpoints.get(), &course_data.get().mapfk.to_string(), &course_data.get().course_name.to_string(), course_data.get().course_id, course_data.get().reverse).await.unwrap_or_default());
-                records.set(data.get().records.clone());
+                checkpoints: cp,
+                limit: RECORDS_PER_PAGE,
+                offset: page_number * RECORDS_PER_PAGE,
+                ..Default::default()
+            };
+            let result = api.records(course_id, &query).await;
+
+            //NOTE: After quick clicks a slower response can arrive for a course, record type or page that's no longer picked
+            if (*state.get_untracked(), *checkpoints.get_untracked(), *page.get_untracked()) != (course_id, cp, page_number)
+            {
+                return;
             }
+            data.set(unwrap_or_report(cx, result));
+            records_count.set(data.get().records_count);
+            records.set(data.get().records.clone());
         });
     });
 
@@ -163,6 +137,7 @@ async fn RecordsComponent<G: Html>(cx: Scope<'_>, map_name: String) -> View<G>
                     let views = View::new_fragment(records.get().as_ref().clone().into_iter().enumerate().map(|(i, record)| 
                     {   
                         let i = i + 1;
+                        let rank = record.rank;
 
                         //NOTE: Alternating background color for each row
                         let bg = if i % 2 == 0 
@@ -173,7 +148,7 @@ async fn RecordsComponent<G: Html>(cx: Scope<'_>, map_name: String) -> View<G>
                             "bg-secondary"
                         };
                         
-                        let steamid = record.steamid.clone();
+                        let steamid = record.steamid;
 
                         view!
                         {
@@ -182,12 +157,13 @@ async fn RecordsComponent<G: Html>(cx: Scope<'_>, map_name: String) -> View<G>
                             {
                                 td(class="px-8 py-4 whitespace-nowrap text-sm xl:text-base 3xl:text-xl font-bold text-white")
                                 {
-                                    (i)
+                                    (rank)
                                 }
                                 a(href=format!("/profile/{}", steamid))
                                 {
                                     td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden hover:scale-110 hover:text-primary duration-150 hover:cursor-pointer text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
                                     {
+                                        PlayerIcons(avatar=record.avatar.clone(), country=record.country.clone())
                                         (record.player_name)
                                     }
                                 }
@@ -197,7 +173,7 @@ async fn RecordsComponent<G: Html>(cx: Scope<'_>, map_name: String) -> View<G>
                                 }
                                 td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden hover:scale-110 hover:text-primary duration-150 hover:cursor-pointer text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
                                 {
-                                    (record.date)
+                                    (record.date.clone().unwrap_or_default())
                                 }
                                 td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden hover:scale-110 hover:text-primary duration-150 hover:cursor-pointer text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
                                 {
@@ -218,14 +194,202 @@ async fn RecordsComponent<G: Html>(cx: Scope<'_>, map_name: String) -> View<G>
     }
 }
 
+#[component]
+async fn WrHistoryComponent<G: Html>(cx: Scope<'_>) -> View<G>
+{
+    let api = use_context::<ApiClient>(cx);
+    let state = use_context::<RcSignal<i32>>(cx);
+    let checkpoints = use_context::<RcSignal<bool>>(cx);
+    let wrs = create_signal(cx, Vec::<WrEntry>::new());
+
+    create_effect(cx, move ||
+    {
+        state.track();
+        checkpoints.track();
+        spawn_local_scoped(cx, async move
+        {
+            if *state.get() != -1
+            {
+                let query = HistoryQuery { checkpoints: *checkpoints.get() };
+                wrs.set(unwrap_or_report(cx, api.wr_history(*state.get(), &query).await).wrs);
+            }
+        });
+    });
+
+    view!
+    {
+        cx,
+        tbody
+        {
+            (
+                {
+                    let views = View::new_fragment(wrs.get().as_ref().clone().into_iter().enumerate().map(|(i, wr)|
+                    {
+                        let i = i + 1;
+
+                        //NOTE: Alternating background color for each row
+                        let bg = if i % 2 == 0
+                        {
+                            "bg-ternary"
+                        }
+                        else {
+                            "bg-secondary"
+                        };
+
+                        let steamid = wr.steamid;
+
+                        view!
+                        {
+                            cx,
+                            tr(class=bg)
+                            {
+                                a(href=format!("/profile/{}", steamid))
+                                {
+                                    td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden hover:scale-110 hover:text-primary duration-150 hover:cursor-pointer text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
+                                    {
+                                        (wr.player_name)
+                                    }
+                                }
+                                td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
+                                {
+                                    (wr.course_time)
+                                }
+                                td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
+                                {
+                                    (wr.date.clone().unwrap_or_default())
+                                }
+                                td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
+                                {
+                                    (format_stood(wr.stood_seconds))
+                                }
+                                td(class="font-bold max-w-[300px] text-ellipsis overflow-hidden text-sm xl:text-base 3xl:text-xl text-gray-400 px-8 py-4")
+                                {
+                                    (wr.diff.map(|diff| diff.to_string()).unwrap_or_default())
+                                }
+                            }
+                        }
+                    }).collect());
+
+                    view!
+                    {
+                        cx,
+                        (views)
+                    }
+                }
+            )
+        }
+    }
+}
+
+#[component]
+fn RecordsTable<G: Html>(cx: Scope) -> View<G>
+{
+    view!
+    {
+        cx,
+        div(class="shadow-2xl overflow-x-auto")
+        {
+            div(class="inline-block min-w-full")
+            {
+                div(class="overflow-hidden", id="records")
+                {
+                    table(class="rounded-lg min-w-full")
+                    {
+                        thead(class="bg-primary")
+                        {
+                            tr
+                            {
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"#"}
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Player"}
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Time"}
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Date"}
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"WR"}
+                            }
+                        }
+                        RecordsComponent {}
+                    }
+                }
+            }
+        }
+        PageControls {}
+    }
+}
+
+#[component]
+fn WrHistoryTable<G: Html>(cx: Scope) -> View<G>
+{
+    view!
+    {
+        cx,
+        div(class="shadow-2xl overflow-x-auto")
+        {
+            div(class="inline-block min-w-full")
+            {
+                div(class="overflow-hidden")
+                {
+                    table(class="rounded-lg min-w-full")
+                    {
+                        thead(class="bg-primary")
+                        {
+                            tr
+                            {
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Player"}
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Time"}
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Date"}
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Stood for"}
+                                th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Improvement"}
+                            }
+                        }
+                        WrHistoryComponent {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PageControls<G: Html>(cx: Scope) -> View<G>
+{
+    let page = use_context::<RcSignal<u32>>(cx);
+    let records_count = use_context::<RcSignal<usize>>(cx);
+    let page_count = create_memo(cx, move || (*records_count.get() as u32).div_ceil(RECORDS_PER_PAGE).max(1));
+
+    view!
+    {
+        cx,
+        div(class="flex justify-between items-center pt-4")
+        {
+            button(on:click=move |_| if *page.get() > 0 { page.set(*page.get() - 1) }, class="font-bold text-primary hover:bg-primary hover:text-secondary duration-150 hover:cursor-pointer bg-transparent border-2 border-solid border-primary px-4 py-2")
+            {
+                "Previous"
+            }
+            p(class="text-sm font-bold text-primary")
+            {
+                "Page " (*page.get() + 1) " of " (page_count.get()) " (" (records_count.get()) " records)"
+            }
+            button(on:click=move |_| if *page.get() + 1 < *page_count.get() { page.set(*page.get() + 1) }, class="font-bold text-primary hover:bg-primary hover:text-secondary duration-150 hover:cursor-pointer bg-transparent border-2 border-solid border-primary px-4 py-2")
+            {
+                "Next"
+            }
+        }
+    }
+}
+
 #[component(inline_props)]
 pub fn RecordsPage<G: Html>(cx: Scope, map_name: String) -> View<G>
 {
-    let course_signal = create_rc_signal(String::new());
+    let course_signal = create_rc_signal(-1i32);
     let cp_signal = create_rc_signal(false);
+    let page_signal = create_rc_signal(0u32);
+    let records_count = create_rc_signal(0usize);
+    let tab_signal = create_rc_signal(RecordsTab::Records);
     provide_context(cx, cp_signal.clone());
     provide_context(cx, course_signal);
-    let map_name_clone = map_name.clone();
+    provide_context(cx, page_signal);
+    provide_context(cx, records_count);
+    provide_context(cx, tab_signal);
+    let tab = use_context::<RcSignal<RecordsTab>>(cx);
 
     view!
     {
@@ -254,6 +418,17 @@ pub fn RecordsPage<G: Html>(cx: Scope, map_name: String) -> View<G>
                             "Checkpoints"
                         }
                     }
+                    div(class="flex gap-2 mb-6")
+                    {
+                        button(on:click=move |_| tab.set(RecordsTab::Records), class=if *tab.get() == RecordsTab::Records { "font-bold bg-primary text-secondary border-2 border-solid border-primary px-2 py-2 duration-150" } else { "font-bold text-primary hover:bg-primary hover:text-secondary hover:cursor-pointer bg-transparent border-2 border-solid border-primary px-2 py-2 duration-150" })
+                        {
+                            "Records"
+                        }
+                        button(on:click=move |_| tab.set(RecordsTab::WrHistory), class=if *tab.get() == RecordsTab::WrHistory { "font-bold bg-primary text-secondary border-2 border-solid border-primary px-2 py-2 duration-150" } else { "font-bold text-primary hover:bg-primary hover:text-secondary hover:cursor-pointer bg-transparent border-2 border-solid border-primary px-2 py-2 duration-150" })
+                        {
+                            "WR history"
+                        }
+                    }
                     div(class="flex flex-col gap-6")
                     {
                         CoursesComponent(map_name=map_name)
@@ -261,30 +436,11 @@ pub fn RecordsPage<G: Html>(cx: Scope, map_name: String) -> View<G>
                 }
                 div(class="max-w-[1140px] flex flex-col")
                 {
-                    div(class="shadow-2xl overflow-x-auto")
+                    (match *tab.get()
                     {
-                        div(class="inline-block min-w-full")
-                        {
-                            div(class="overflow-hidden", id="records")
-                            {
-                                table(class="rounded-lg min-w-full")
-                                {
-                                    thead(class="bg-primary")
-                                    {
-                                        tr 
-                                        {
-                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"#"}
-                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Player"}
-                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Time"}
-                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"Date"}
-                                            th(scope="col", class="text-sm xl:text-base 3xl:text-xl font-bold text-secondary px-8 py-4 text-left"){"WR"}
-                                        }
-                                    }
-                                    RecordsComponent(map_name=map_name_clone)
-                                }
-                            }   
-                        }
-                    }
+                        RecordsTab::Records => view! { cx, RecordsTable {} },
+                        RecordsTab::WrHistory => view! { cx, WrHistoryTable {} },
+                    })
                     p(class="text-right text-sm font-bold text-primary pt-2 pb-4")
                     {
                         "Toplist by Menko and GoldenNinja"
@@ -0,0 +1,317 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::Sub;
+use std::str::FromStr;
+
+const MILLIS_PER_SECOND: u64 = 1000;
+const MILLIS_PER_MINUTE: u64 = 60 * MILLIS_PER_SECOND;
+const MILLIS_PER_HOUR: u64 = 60 * MILLIS_PER_MINUTE;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CourseTimeError(String);
+
+impl fmt::Display for CourseTimeError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "'{}' is not a valid course time", self.0)
+    }
+}
+
+impl std::error::Error for CourseTimeError {}
+
+//NOTE: Time it took to finish a course, in milliseconds
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CourseTime(u32);
+
+impl CourseTime
+{
+    pub fn from_millis(millis: u32) -> Self
+    {
+        CourseTime(millis)
+    }
+
+    pub fn as_millis(&self) -> u32
+    {
+        self.0
+    }
+
+    //NOTE: Format of the course_time column, hours are not wrapped at 24
+    pub fn to_db_string(self) -> String
+    {
+        let (hours, minutes, seconds, millis) = split_millis(self.0 as u64);
+        format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, seconds, millis)
+    }
+}
+
+//NOTE: Signed difference between two course times in milliseconds. Negative on reverse courses where the WR is the longest time
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CourseTimeDiff(i64);
+
+impl CourseTimeDiff
+{
+    pub fn as_millis(&self) -> i64
+    {
+        self.0
+    }
+}
+
+impl Sub for CourseTime
+{
+    type Output = CourseTimeDiff;
+
+    fn sub(self, rhs: CourseTime) -> CourseTimeDiff
+    {
+        CourseTimeDiff(self.0 as i64 - rhs.0 as i64)
+    }
+}
+
+fn split_millis(millis: u64) -> (u64, u64, u64, u64)
+{
+    (millis / MILLIS_PER_HOUR, (millis % MILLIS_PER_HOUR) / MILLIS_PER_MINUTE, (millis % MILLIS_PER_MINUTE) / MILLIS_PER_SECOND, millis % MILLIS_PER_SECOND)
+}
+
+//NOTE: KZ style, mm:ss.cc below an hour and h:mm:ss.cc above. Milliseconds are truncated to centiseconds like the in-game timer
+fn fmt_millis(f: &mut fmt::Formatter<'_>, millis: u64) -> fmt::Result
+{
+    let (hours, minutes, seconds, millis) = split_millis(millis);
+    if hours > 0
+    {
+        write!(f, "{}:{:02}:{:02}.{:02}", hours, minutes, seconds, millis / 10)
+    }
+    else
+    {
+        write!(f, "{:02}:{:02}.{:02}", minutes, seconds, millis / 10)
+    }
+}
+
+impl fmt::Display for CourseTime
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        fmt_millis(f, self.0 as u64)
+    }
+}
+
+impl fmt::Display for CourseTimeDiff
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", if self.0 < 0 { "-" } else { "+" })?;
+        fmt_millis(f, self.0.unsigned_abs())
+    }
+}
+
+//NOTE: Accepts h:mm:ss.fff, mm:ss.fff and ss.fff with any number of fractional digits, which covers both the
+//course_time column and the Display format
+impl FromStr for CourseTime
+{
+    type Err = CourseTimeError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err>
+    {
+        let error = || CourseTimeError(input.to_string());
+        let trimmed = input.trim();
+
+        let (whole, fraction) = match trimmed.split_once('.')
+        {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (trimmed, ""),
+        };
+        if !fraction.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(error());
+        }
+        //NOTE: Pad or truncate the fraction to exactly three digits, e.g. .5 -> 500 and .123456 -> 123
+        let millis = fraction.bytes().chain(std::iter::repeat(b'0')).take(3).fold(0, |acc, b| acc * 10 + (b - b'0') as u64);
+
+        let parts: Vec<&str> = whole.split(':').collect();
+        if parts.len() > 3 || parts.iter().any(|part| part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Err(error());
+        }
+        let mut units = parts.iter().rev().map(|part| part.parse::<u64>());
+        let seconds = units.next().unwrap_or(Ok(0)).map_err(|_| error())?;
+        let minutes = units.next().unwrap_or(Ok(0)).map_err(|_| error())?;
+        let hours = units.next().unwrap_or(Ok(0)).map_err(|_| error())?;
+        if parts.len() > 1 && (seconds >= 60 || (parts.len() > 2 && minutes >= 60))
+        {
+            return Err(error());
+        }
+
+        let total = hours.checked_mul(MILLIS_PER_HOUR)
+            .and_then(|total| total.checked_add(minutes * MILLIS_PER_MINUTE))
+            .and_then(|total| total.checked_add(seconds * MILLIS_PER_SECOND))
+            .and_then(|total| total.checked_add(millis))
+            .and_then(|total| u32::try_from(total).ok())
+            .ok_or_else(error)?;
+
+        Ok(CourseTime(total))
+    }
+}
+
+//NOTE: The Display format, a sign followed by a course time
+impl FromStr for CourseTimeDiff
+{
+    type Err = CourseTimeError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err>
+    {
+        let trimmed = input.trim();
+        let (sign, time) = match trimmed.strip_prefix('-')
+        {
+            Some(time) => (-1, time),
+            None => (1, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let time = time.parse::<CourseTime>().map_err(|_| CourseTimeError(input.to_string()))?;
+
+        Ok(CourseTimeDiff(sign * time.0 as i64))
+    }
+}
+
+impl Serialize for CourseTime
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl Serialize for CourseTimeDiff
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        serializer.collect_str(self)
+    }
+}
+
+//NOTE: Deserializes from either a number of milliseconds or a formatted string
+impl<'de> Deserialize<'de> for CourseTime
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr
+        {
+            Millis(u32),
+            Formatted(String),
+        }
+
+        match Repr::deserialize(deserializer)?
+        {
+            Repr::Millis(millis) => Ok(CourseTime(millis)),
+            Repr::Formatted(time) => time.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CourseTimeDiff
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        let input = String::deserialize(deserializer)?;
+        input.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn time(input: &str) -> CourseTime
+    {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_db_column()
+    {
+        assert_eq!(time("00:01:23.45"), CourseTime::from_millis(83_450));
+        assert_eq!(time("00:00:00.5"), CourseTime::from_millis(500));
+        assert_eq!(time("01:02:03.123456"), CourseTime::from_millis(3_723_123));
+        assert_eq!(time("00:00:07"), CourseTime::from_millis(7_000));
+    }
+
+    #[test]
+    fn parses_display_format()
+    {
+        for millis in [0, 10, 59_990, 83_450, 3_599_990, 3_723_120, 90_061_990]
+        {
+            let course_time = CourseTime::from_millis(millis);
+            assert_eq!(time(&course_time.to_string()), course_time);
+            assert_eq!(time(&course_time.to_db_string()), course_time);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_input()
+    {
+        for input in ["", ":", "1:2:3:4", "00:61:00.00", "00:00:60.00", "aa:bb:cc", "00:00:01.x", "-00:00:01.00", "00::01.00", "1200:00:00.00"]
+        {
+            assert!(input.parse::<CourseTime>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn displays_kz_style()
+    {
+        assert_eq!(time("00:01:23.45").to_string(), "01:23.45");
+        assert_eq!(time("00:00:00.009").to_string(), "00:00.00");
+        assert_eq!(time("00:59:59.999").to_string(), "59:59.99");
+        assert_eq!(time("01:00:00.00").to_string(), "1:00:00.00");
+    }
+
+    #[test]
+    fn handles_times_over_24_hours()
+    {
+        let long = time("25:01:02.50");
+
+        assert_eq!(long.as_millis(), 90_062_500);
+        assert_eq!(long.to_string(), "25:01:02.50");
+        assert_eq!(long.to_db_string(), "25:01:02.500");
+        assert_eq!((long - time("00:01:02.50")).to_string(), "+25:00:00.00");
+    }
+
+    #[test]
+    fn reverse_course_diff_is_negative()
+    {
+        //NOTE: On reverse courses the WR is the longest time so every other time is behind it by a negative amount
+        let wr = time("00:10:00.00");
+        let other = time("00:07:30.25");
+
+        assert_eq!((other - wr).as_millis(), -149_750);
+        assert_eq!((other - wr).to_string(), "-02:29.75");
+        assert_eq!((wr - other).to_string(), "+02:29.75");
+    }
+
+    #[test]
+    fn sub_second_ties()
+    {
+        let wr = time("00:00:42.10");
+
+        assert_eq!((time("00:00:42.10") - wr).to_string(), "+00:00.00");
+        assert_eq!((time("00:00:42.11") - wr).to_string(), "+00:00.01");
+        assert_eq!((time("00:00:42.105") - wr).as_millis(), 5);
+        assert!(time("00:00:42.105") > wr);
+        assert_eq!(time("00:00:42.105").to_string(), wr.to_string());
+    }
+
+    #[test]
+    fn serde_accepts_numbers_and_strings()
+    {
+        let course_time = CourseTime::from_millis(83_450);
+
+        assert_eq!(serde_json::to_string(&course_time).unwrap(), "\"01:23.45\"");
+        assert_eq!(serde_json::from_str::<CourseTime>("83450").unwrap(), course_time);
+        assert_eq!(serde_json::from_str::<CourseTime>("\"01:23.45\"").unwrap(), course_time);
+        assert_eq!(serde_json::from_str::<CourseTime>("\"00:01:23.450\"").unwrap(), course_time);
+        assert!(serde_json::from_str::<CourseTime>("\"later\"").is_err());
+        assert!(serde_json::from_str::<CourseTime>("-5").is_err());
+        assert_eq!(serde_json::to_string(&(CourseTime::from_millis(0) - course_time)).unwrap(), "\"-01:23.45\"");
+        assert_eq!(serde_json::from_str::<CourseTimeDiff>("\"-01:23.45\"").unwrap(), CourseTime::from_millis(0) - course_time);
+        assert_eq!(serde_json::from_str::<CourseTimeDiff>("\"+1:00:00.00\"").unwrap().as_millis(), 3_600_000);
+        assert!(serde_json::from_str::<CourseTimeDiff>("\"--01:23.45\"").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::course_time::CourseTime;
+use crate::steamid::SteamId;
+use sqlx::database::{HasArguments, HasValueRef};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::{Database, Decode, Encode, Type};
+
+//NOTE: Only built with the sqlx feature so the frontend doesn't pull in sqlx
+
+//NOTE: The database stores SteamID3 strings, e.g. [U:1:19432566]
+impl<DB: Database> Type<DB> for SteamId
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo
+    {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool
+    {
+        <String as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for SteamId
+where
+    &'r str: Decode<'r, DB>,
+{
+    fn decode(value: <DB as HasValueRef<'r>>::ValueRef) -> Result<Self, BoxDynError>
+    {
+        let steamid = <&str as Decode<DB>>::decode(value)?;
+        Ok(steamid.parse()?)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for SteamId
+where
+    String: Encode<'q, DB>,
+{
+    fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull
+    {
+        <String as Encode<DB>>::encode(self.steamid3(), buf)
+    }
+}
+
+//NOTE: The database stores times in the format of to_db_string
+impl<DB: Database> Type<DB> for CourseTime
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo
+    {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool
+    {
+        <String as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for CourseTime
+where
+    &'r str: Decode<'r, DB>,
+{
+    fn decode(value: <DB as HasValueRef<'r>>::ValueRef) -> Result<Self, BoxDynError>
+    {
+        let time = <&str as Decode<DB>>::decode(value)?;
+        Ok(time.parse()?)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for CourseTime
+where
+    String: Encode<'q, DB>,
+{
+    fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull
+    {
+        <String as Encode<DB>>::encode(self.to_db_string(), buf)
+    }
+}
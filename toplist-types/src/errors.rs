@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+//NOTE: Machine readable part of an error response, clients should check it instead of the message
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode
+{
+    BadRequest,
+    NotFound,
+    Unauthorized,
+    RateLimited,    //NOTE: The Steam API is rate limiting the backend, worth retrying later
+    Upstream,       //NOTE: The Steam API failed
+    Internal,
+    #[serde(other)]
+    Unknown,        //NOTE: Codes added by a newer backend
+}
+
+//NOTE: Body of every error response, {"error": {"code": ..., "message": ..., "request_id": ...}}
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorResponse
+{
+    pub error: ErrorDetails,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorDetails
+{
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,     //NOTE: Also in the x-request-id header and in the backend's logs of the request
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn error_codes()
+    {
+        let body = ErrorResponse { error: ErrorDetails { code: ErrorCode::RateLimited, message: "Steam API is busy.".to_string(), request_id: None } };
+        assert_eq!(serde_json::to_string(&body).unwrap(), r#"{"error":{"code":"rate_limited","message":"Steam API is busy."}}"#);
+
+        let body: ErrorResponse = serde_json::from_str(r#"{"error":{"code":"teapot","message":"","request_id":"abc"}}"#).unwrap();
+        assert_eq!(body.error.code, ErrorCode::Unknown);
+        assert_eq!(body.error.request_id.as_deref(), Some("abc"));
+    }
+}
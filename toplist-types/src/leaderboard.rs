@@ -0,0 +1,71 @@
+use crate::steamid::SteamId;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_LEADERBOARD_LIMIT: u32 = 100;
+
+//NOTE: Query of /api/leaderboard
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LeaderboardQuery
+{
+    pub checkpoints: bool,
+    pub limit: u32,
+    pub offset: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,    //NOTE: ISO 3166 country code, ranks only the players from that country
+}
+
+impl Default for LeaderboardQuery
+{
+    fn default() -> Self
+    {
+        LeaderboardQuery { checkpoints: false, limit: DEFAULT_LEADERBOARD_LIMIT, offset: 0, country: None }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Leaderboard
+{
+    pub players_count: usize,
+    pub players: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry
+{
+    pub rank: usize,
+    pub steamid: SteamId,
+    pub player_name: String,
+    pub avatar: Option<String>,
+    pub country: Option<String>,
+    pub points: u64,
+    pub wrs: u32,
+    pub top10: u32,
+    pub courses: u32,
+}
+
+//NOTE: Query of /api/countries
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct CountriesQuery
+{
+    pub checkpoints: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Countries
+{
+    pub countries_count: usize,
+    pub countries: Vec<CountryEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CountryEntry
+{
+    pub rank: usize,
+    pub country: String,            //NOTE: ISO 3166 country code
+    pub players: u32,
+    pub points: u64,
+    pub wrs: u32,
+    pub top10: u32,
+}
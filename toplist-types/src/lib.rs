@@ -0,0 +1,21 @@
+//NOTE: Requests and responses of the toplist API, shared by the backend and the frontend so both sides agree on the
+//JSON. Everything here has to build for wasm, database support is behind the sqlx feature
+pub mod course_time;
+#[cfg(feature = "sqlx")]
+mod database;
+pub mod errors;
+pub mod leaderboard;
+pub mod maps;
+pub mod players;
+pub mod records;
+pub mod servers;
+pub mod steamid;
+
+pub use course_time::{CourseTime, CourseTimeDiff};
+pub use errors::{ErrorCode, ErrorDetails, ErrorResponse};
+pub use leaderboard::{Countries, CountriesQuery, CountryEntry, Leaderboard, LeaderboardEntry, LeaderboardQuery};
+pub use maps::{Course, CourseName, CourseNames, Courses, Map, Maps};
+pub use players::{Player, PlayerRecord, PlayerRecords, Players, SteamResponse};
+pub use records::{HistoryEntry, HistoryQuery, RecentQuery, RecentRecord, RecentRecords, Record, RecordHistory, RecordSort, RecordSubmission, Records, RecordsQuery, SubmissionResult, WrEntry, WrHistory};
+pub use servers::{NewServer, PurgedRecords, ServerCredentials, ServerInfo, Servers};
+pub use steamid::{SteamId, SteamIdError};
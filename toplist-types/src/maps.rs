@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Maps
+{
+    pub maps: Vec<Map>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Map
+{
+    pub map_name: String,
+    pub times_played: Option<i32>,
+    pub time_added: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CourseNames
+{
+    pub course_count: usize,
+    pub course_names: Vec<CourseName>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct CourseName
+{
+    pub course_id: i32,
+    pub course_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Courses
+{
+    pub course_count: usize,
+    pub courses: Vec<Course>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Course
+{
+    pub course_id: i32,
+    pub course_name: String,
+    pub mapfk: String,
+    pub reverse: i8,
+}
@@ -0,0 +1,64 @@
+use crate::course_time::CourseTime;
+use serde::{Deserialize, Serialize};
+
+//NOTE: GetPlayerSummaries response of the Steam Web API, /api/player/info passes it through as is
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct SteamResponse
+{
+    pub response: Players,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Players
+{
+    pub players: Vec<Player>,       //NOTE: GetPlayerSummaries takes up to 100 SteamIDs at a time, players it doesn't know are left out
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Player
+{
+    pub steamid: String,                //NOTE: 64 bit SteamID
+    pub communityvisibilitystate: u32,  //NOTE: Profile visibility. 1 - private, 2 - friends, 3 - friends of friends, 4 - logged in Steam users, 5 - public
+    pub profilestate: u32,              //NOTE: If set, indicates the user has a community profile configured (will be set to 1)
+    pub personaname: String,            //NOTE: Display name
+    pub lastlogoff: Option<u32>,        //NOTE: Unix timestamp of last logoff
+    pub profileurl: String,             //NOTE: Full URL to the Steam profile
+    pub avatar: String,                 //NOTE: Full URL to the 32x32 pixels version of the avatar
+    pub avatarmedium: String,           //NOTE: 64x64 version of the avatar
+    pub avatarfull: String,             //NOTE: 184x184 version of the avatar
+    pub avatarhash: String,             //NOTE: Hash of the avatar (used in URLs in the avatar/avatarmedium/avatarfull fields)
+    pub personastate: u32,              //NOTE: User's status: 0 - offline, 1 - online, 2 - busy,3 - away, 4 -Snooze, 5 - looking to trade, 6 - looking to play
+    pub commentpermission: Option<u32>, //NOTE: Are comments allowed on the profile?
+    pub realname: Option<String>,       //NOTE: Name of the player
+    pub primaryclanid: Option<String>,  //NOTE: 64 bit ID of the user's primary group
+    pub timecreated: Option<u32>,       //NOTE: Unix timestamp of when the profile was created
+    pub personastateflags: Option<u32>, //NOTE: ?????
+    pub loccountrycode: Option<String>, //NOTE: ISO 3166 country code
+    pub locstatecode: Option<String>,   //NOTE: Code of the area of the country
+    pub loccityid: Option<u32>,         //NOTE: ID of the city the player is from
+    pub gameid: Option<String>,         //NOTE: ID of the game the player is currently playing
+    pub gameextrainfo: Option<String>,  //NOTE: Name of the game the player is currently playing
+    pub gameserverip: Option<String>,   //NOTE: IP of the server the player is currently on
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct PlayerRecords
+{
+    pub records_count: usize,
+    pub points: u64,
+    pub records: Vec<PlayerRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlayerRecord
+{
+    pub course_id: i32,
+    pub map_name: String,
+    pub course_name: String,
+    pub course_time: CourseTime,
+    pub date: Option<String>,
+    pub rank: u64,
+    pub field_size: u64,
+    pub diff: String,
+    pub points: u64,
+}
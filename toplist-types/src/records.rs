@@ -0,0 +1,171 @@
+use crate::course_time::{CourseTime, CourseTimeDiff};
+use crate::steamid::SteamId;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_RECORDS_LIMIT: u32 = 50;
+pub const DEFAULT_RECENT_LIMIT: u32 = 20;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordSort
+{
+    Time,
+    Date,
+    Player,
+}
+
+impl RecordSort
+{
+    pub fn as_str(&self) -> &'static str
+    {
+        match self
+        {
+            RecordSort::Time => "time",
+            RecordSort::Date => "date",
+            RecordSort::Player => "player",
+        }
+    }
+}
+
+//NOTE: Query of /api/courses/:course_id/records
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RecordsQuery
+{
+    pub checkpoints: bool,
+    pub limit: u32,
+    pub offset: u32,
+    pub sort: RecordSort,
+}
+
+impl Default for RecordsQuery
+{
+    fn default() -> Self
+    {
+        RecordsQuery { checkpoints: false, limit: DEFAULT_RECORDS_LIMIT, offset: 0, sort: RecordSort::Time }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Records
+{
+    pub records_count: usize,       //NOTE: Total number of records on the course, not just the ones on this page
+    pub records: Vec<Record>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Record
+{
+    pub rank: u64,
+    pub player_name: String,
+    pub steamid: SteamId,
+    pub course_time: CourseTime,
+    pub date: Option<String>,
+    pub diff: String,
+    pub avatar: Option<String>,     //NOTE: 32x32 Steam avatar, None until the profile refresher has looked the player up
+    pub country: Option<String>,    //NOTE: ISO 3166 country code from the Steam profile
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct WrHistory
+{
+    pub wrs_count: usize,
+    pub wrs: Vec<WrEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WrEntry
+{
+    pub steamid: SteamId,
+    pub player_name: String,
+    pub course_time: CourseTime,
+    pub date: Option<String>,
+    pub stood_seconds: i64,             //NOTE: Until the next WR was set, or until now for the current WR
+    pub diff: Option<CourseTimeDiff>,   //NOTE: Difference to the previous WR, None for the first WR set on the course
+}
+
+//NOTE: Query of /api/recent
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RecentQuery
+{
+    pub checkpoints: bool,
+    pub limit: u32,
+}
+
+impl Default for RecentQuery
+{
+    fn default() -> Self
+    {
+        RecentQuery { checkpoints: false, limit: DEFAULT_RECENT_LIMIT }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct RecentRecords
+{
+    pub records_count: usize,
+    pub records: Vec<RecentRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecentRecord
+{
+    pub map_name: String,
+    pub course_id: i32,
+    pub course_name: String,
+    pub player_name: String,
+    pub steamid: SteamId,
+    pub course_time: CourseTime,
+    pub date: Option<String>,
+    pub wr: bool,                   //NOTE: Whether the time was a new WR when it was set
+}
+
+//NOTE: Query of the WR and personal best history routes
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct HistoryQuery
+{
+    pub checkpoints: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct RecordHistory
+{
+    pub course_id: i32,
+    pub course_name: String,
+    pub map_name: String,
+    pub history_count: usize,
+    pub history: Vec<HistoryEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryEntry
+{
+    pub course_time: CourseTime,
+    pub millis: u32,
+    pub date: Option<String>,
+    pub timestamp: i64,                 //NOTE: Unix timestamp of date_set, for plotting
+    pub diff: Option<CourseTimeDiff>,   //NOTE: Difference to the previous personal best, None for the first time set
+}
+
+//NOTE: A finished run sent by a game server. Pro runs can't have teleports
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordSubmission
+{
+    pub steamid: String,
+    pub player_name: String,
+    pub course_id: i32,
+    pub course_time: CourseTime,
+    pub checkpoints: bool,
+    pub teleports: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SubmissionResult
+{
+    pub improved: bool,             //NOTE: Whether the time replaced the player's personal best
+    pub rank: u64,                  //NOTE: Where the submitted time places on the course
+    pub wr: bool,
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NewServer
+{
+    pub name: String,
+}
+
+//NOTE: The only time the API key is shown, only its argon2 hash is stored
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ServerCredentials
+{
+    pub server_id: i32,
+    pub name: String,
+    pub api_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Servers
+{
+    pub servers_count: usize,
+    pub servers: Vec<ServerInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ServerInfo
+{
+    pub server_id: i32,
+    pub name: String,
+    pub created_at: Option<String>,
+    pub key_rotated_at: Option<String>,
+    pub revoked_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct PurgedRecords
+{
+    pub records: u64,
+    pub records_cp: u64,
+}
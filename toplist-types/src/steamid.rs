@@ -0,0 +1,327 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+//NOTE: SteamID64 bit layout, from most to least significant:
+//universe (8 bits) | account type (4 bits) | instance (20 bits) | account id (32 bits)
+//For example: [U:1:19432566] -> (1 << 56) | (1 << 52) | (1 << 32) | 19432566 = 76561197979698294
+const UNIVERSE_SHIFT: u64 = 56;
+const ACCOUNT_TYPE_SHIFT: u64 = 52;
+const INSTANCE_SHIFT: u64 = 32;
+
+const UNIVERSE_PUBLIC: u64 = 1;
+const UNIVERSE_MAX: u64 = 4;                //NOTE: 1 - public, 2 - beta, 3 - internal, 4 - dev. 0 is invalid
+const ACCOUNT_TYPE_INDIVIDUAL: u64 = 1;     //NOTE: Only individual accounts can set records
+const INSTANCE_DESKTOP: u64 = 1;
+
+const PROFILES_PREFIX: &str = "steamcommunity.com/profiles/";
+const VANITY_PREFIX: &str = "steamcommunity.com/id/";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SteamIdError
+{
+    Malformed(String),
+    InvalidUniverse(u64),
+    InvalidAccountType(u64),
+    Vanity(String),                         //NOTE: Vanity URLs can only be resolved through the Steam API
+}
+
+impl fmt::Display for SteamIdError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            SteamIdError::Malformed(input) => write!(f, "'{}' is not a valid SteamID", input),
+            SteamIdError::InvalidUniverse(universe) => write!(f, "SteamID universe {} is not valid", universe),
+            SteamIdError::InvalidAccountType(account_type) => write!(f, "SteamID account type {} is not an individual account", account_type),
+            SteamIdError::Vanity(name) => write!(f, "vanity URL '{}' has to be resolved through the Steam API", name),
+        }
+    }
+}
+
+impl std::error::Error for SteamIdError {}
+
+//NOTE: Stored as a SteamID64. The database keeps SteamID3 strings and the frontend links profiles by SteamID64
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SteamId(u64);
+
+impl SteamId
+{
+    pub fn from_account_id(universe: u64, account_id: u32) -> Result<Self, SteamIdError>
+    {
+        if universe == 0 || universe > UNIVERSE_MAX
+        {
+            return Err(SteamIdError::InvalidUniverse(universe));
+        }
+
+        Ok(SteamId((universe << UNIVERSE_SHIFT) | (ACCOUNT_TYPE_INDIVIDUAL << ACCOUNT_TYPE_SHIFT) | (INSTANCE_DESKTOP << INSTANCE_SHIFT) | account_id as u64))
+    }
+
+    pub fn from_steamid64(steamid64: u64) -> Result<Self, SteamIdError>
+    {
+        let universe = steamid64 >> UNIVERSE_SHIFT;
+        let account_type = (steamid64 >> ACCOUNT_TYPE_SHIFT) & 0xF;
+
+        if universe == 0 || universe > UNIVERSE_MAX
+        {
+            return Err(SteamIdError::InvalidUniverse(universe));
+        }
+        if account_type != ACCOUNT_TYPE_INDIVIDUAL
+        {
+            return Err(SteamIdError::InvalidAccountType(account_type));
+        }
+
+        Ok(SteamId(steamid64))
+    }
+
+    pub fn universe(&self) -> u64
+    {
+        self.0 >> UNIVERSE_SHIFT
+    }
+
+    pub fn account_id(&self) -> u32
+    {
+        self.0 as u32
+    }
+
+    pub fn steamid64(&self) -> u64
+    {
+        self.0
+    }
+
+    //NOTE: KZMod runs on the Orange Box engine which reports public accounts with universe 0 in SteamID2, the other
+    //universes keep their number so they parse back to the same account
+    pub fn steamid2(&self) -> String
+    {
+        let universe = if self.universe() == UNIVERSE_PUBLIC { 0 } else { self.universe() };
+        format!("STEAM_{}:{}:{}", universe, self.account_id() & 1, self.account_id() >> 1)
+    }
+
+    pub fn steamid3(&self) -> String
+    {
+        format!("[U:{}:{}]", self.universe(), self.account_id())
+    }
+
+    pub fn profile_url(&self) -> String
+    {
+        format!("https://{}{}", PROFILES_PREFIX, self.0)
+    }
+
+    fn parse_steamid2(input: &str, body: &str) -> Result<Self, SteamIdError>
+    {
+        let malformed = || SteamIdError::Malformed(input.to_string());
+        let mut parts = body.split(':');
+
+        let universe = parts.next().and_then(|x| x.parse::<u64>().ok()).ok_or_else(malformed)?;
+        let low_bit = parts.next().and_then(|y| y.parse::<u32>().ok()).filter(|y| *y <= 1).ok_or_else(malformed)?;
+        let high_bits = parts.next().and_then(|z| z.parse::<u32>().ok()).filter(|z| *z <= u32::MAX >> 1).ok_or_else(malformed)?;
+        if parts.next().is_some()
+        {
+            return Err(malformed());
+        }
+
+        //NOTE: Older engines report universe 0 for public accounts
+        let universe = if universe == 0 { UNIVERSE_PUBLIC } else { universe };
+        SteamId::from_account_id(universe, (high_bits << 1) | low_bit)
+    }
+
+    fn parse_steamid3(input: &str, body: &str) -> Result<Self, SteamIdError>
+    {
+        let malformed = || SteamIdError::Malformed(input.to_string());
+        let mut parts = body.split(':');
+
+        let letter = parts.next().ok_or_else(malformed)?;
+        let universe = parts.next().and_then(|x| x.parse::<u64>().ok()).ok_or_else(malformed)?;
+        let account_id = parts.next().and_then(|z| z.parse::<u32>().ok()).ok_or_else(malformed)?;
+        if parts.next().is_some()
+        {
+            return Err(malformed());
+        }
+
+        //NOTE: Account type letters, see https://developer.valvesoftware.com/wiki/SteamID#Types_of_Steam_Accounts
+        let account_type = match letter
+        {
+            "U" => return SteamId::from_account_id(universe, account_id),
+            "M" => 2,
+            "G" => 3,
+            "A" => 4,
+            "P" => 5,
+            "C" => 6,
+            "g" => 7,
+            "T" | "L" | "c" => 8,
+            "a" => 10,
+            _ => return Err(malformed()),
+        };
+        Err(SteamIdError::InvalidAccountType(account_type))
+    }
+}
+
+impl FromStr for SteamId
+{
+    type Err = SteamIdError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err>
+    {
+        let trimmed = input.trim();
+
+        //NOTE: Community URLs, with or without the scheme and trailing slash
+        let url = trimmed.trim_start_matches("https://").trim_start_matches("http://").trim_start_matches("www.").trim_end_matches('/');
+        if let Some(profile) = url.strip_prefix(PROFILES_PREFIX)
+        {
+            return profile.parse();
+        }
+        if let Some(vanity) = url.strip_prefix(VANITY_PREFIX)
+        {
+            if vanity.is_empty() || vanity.contains('/')
+            {
+                return Err(SteamIdError::Malformed(input.to_string()));
+            }
+            return Err(SteamIdError::Vanity(vanity.to_string()));
+        }
+
+        if let Some(body) = trimmed.strip_prefix("STEAM_")
+        {
+            return SteamId::parse_steamid2(input, body);
+        }
+        if let Some(body) = trimmed.strip_prefix('[').and_then(|body| body.strip_suffix(']'))
+        {
+            return SteamId::parse_steamid3(input, body);
+        }
+        if !trimmed.is_empty() && trimmed.bytes().all(|b| b.is_ascii_digit())
+        {
+            let steamid64 = trimmed.parse::<u64>().map_err(|_| SteamIdError::Malformed(input.to_string()))?;
+            return SteamId::from_steamid64(steamid64);
+        }
+
+        Err(SteamIdError::Malformed(input.to_string()))
+    }
+}
+
+impl fmt::Display for SteamId
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.0)
+    }
+}
+
+//NOTE: Serialized as a SteamID64 string since JavaScript can't represent 64 bit integers exactly
+impl Serialize for SteamId
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SteamId
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        let input = String::deserialize(deserializer)?;
+        input.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const STEAMID64: u64 = 76561197979698294;
+
+    #[test]
+    fn parses_every_format()
+    {
+        let expected = SteamId::from_steamid64(STEAMID64).unwrap();
+
+        for input in [
+            "76561197979698294",
+            "[U:1:19432566]",
+            "STEAM_0:0:9716283",
+            "STEAM_1:0:9716283",
+            "https://steamcommunity.com/profiles/76561197979698294/",
+            "http://steamcommunity.com/profiles/76561197979698294",
+            "steamcommunity.com/profiles/[U:1:19432566]",
+            "  [U:1:19432566]  ",
+        ]
+        {
+            assert_eq!(input.parse::<SteamId>(), Ok(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_every_format()
+    {
+        for steamid64 in [STEAMID64, 76561197960265729, 76561198000000001, 76561202255233023]
+        {
+            let steamid = SteamId::from_steamid64(steamid64).unwrap();
+
+            assert_eq!(steamid.steamid2().parse::<SteamId>(), Ok(steamid));
+            assert_eq!(steamid.steamid3().parse::<SteamId>(), Ok(steamid));
+            assert_eq!(steamid.to_string().parse::<SteamId>(), Ok(steamid));
+            assert_eq!(steamid.profile_url().parse::<SteamId>(), Ok(steamid));
+        }
+    }
+
+    #[test]
+    fn keeps_the_universe_in_steamid2()
+    {
+        for universe in 2..=UNIVERSE_MAX
+        {
+            let steamid = SteamId::from_account_id(universe, 19432566).unwrap();
+
+            assert_eq!(steamid.steamid2(), format!("STEAM_{}:0:9716283", universe));
+            assert_eq!(steamid.steamid2().parse::<SteamId>(), Ok(steamid));
+        }
+    }
+
+    #[test]
+    fn renders_every_format()
+    {
+        let steamid = SteamId::from_steamid64(STEAMID64).unwrap();
+
+        assert_eq!(steamid.account_id(), 19432566);
+        assert_eq!(steamid.steamid2(), "STEAM_0:0:9716283");
+        assert_eq!(steamid.steamid3(), "[U:1:19432566]");
+        assert_eq!(steamid.to_string(), "76561197979698294");
+        assert_eq!(steamid.profile_url(), "https://steamcommunity.com/profiles/76561197979698294");
+    }
+
+    #[test]
+    fn rejects_malformed_input()
+    {
+        for input in ["", "[U:1:]", "[U:1:abc]", "[U:1:19432566", "U:1:19432566]", "[U:1:2:3]", "STEAM_0:2:1", "STEAM_0:0", "STEAM_0:0:4294967295", "-76561197979698294", "steamcommunity.com/id/", "hello"]
+        {
+            assert!(matches!(input.parse::<SteamId>(), Err(SteamIdError::Malformed(_))), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_universe_and_account_type()
+    {
+        assert_eq!("[U:0:19432566]".parse::<SteamId>(), Err(SteamIdError::InvalidUniverse(0)));
+        assert_eq!("[U:5:19432566]".parse::<SteamId>(), Err(SteamIdError::InvalidUniverse(5)));
+        assert_eq!("STEAM_7:0:1".parse::<SteamId>(), Err(SteamIdError::InvalidUniverse(7)));
+        assert_eq!("19432566".parse::<SteamId>(), Err(SteamIdError::InvalidUniverse(0)));
+        assert_eq!("[G:1:4]".parse::<SteamId>(), Err(SteamIdError::InvalidAccountType(3)));
+        assert_eq!("103582791429521412".parse::<SteamId>(), Err(SteamIdError::InvalidAccountType(7)));
+    }
+
+    #[test]
+    fn reports_vanity_urls()
+    {
+        assert_eq!("https://steamcommunity.com/id/gabelogannewell/".parse::<SteamId>(), Err(SteamIdError::Vanity("gabelogannewell".to_string())));
+    }
+
+    #[test]
+    fn serializes_as_steamid64_string()
+    {
+        let steamid = SteamId::from_steamid64(STEAMID64).unwrap();
+
+        assert_eq!(serde_json::to_string(&steamid).unwrap(), "\"76561197979698294\"");
+        assert_eq!(serde_json::from_str::<SteamId>("\"[U:1:19432566]\"").unwrap(), steamid);
+        assert!(serde_json::from_str::<SteamId>("\"[U:1:x]\"").is_err());
+    }
+}